@@ -0,0 +1,490 @@
+use crate::{
+    channels::{
+        telegram::{self, InlineKeyboardMarkup},
+        truncate,
+    },
+    config,
+    monitors::Monitors,
+    services::{format_time, Notification, NotificationService},
+    status::Registry,
+    subscriptions::{Area, Store, Subscription},
+};
+use chrono::Utc;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+pub mod polling;
+pub mod webhook;
+
+const DEFAULT_RADIUS_KM: f64 = 500.0;
+
+const HELP: &str = "Automatex Notifier commands:
+/status - last check and last error of each service
+/latest quake|launch|flare|cve - the most recent notification
+/next launch - the next scheduled launch
+/mute 2h - pause notifications to this chat (m, h or d; /mute off to resume; admins only in groups)
+/subscribe quake 5.5 - receive a service's notifications here, optionally above a magnitude or CVSS score
+/unsubscribe cve - stop receiving a service's notifications here
+/near 13.75 100.50 300 - only earthquakes within 300 km of a point (or share a location in a private chat; /near off to clear)
+/subscriptions - list this chat's subscriptions
+/help - this message";
+
+#[derive(Debug, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<IncomingMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IncomingMessage {
+    chat: Chat,
+    message_thread_id: Option<i64>,
+    from: Option<User>,
+    // Set instead of `from` when an anonymous administrator writes as the group itself.
+    sender_chat: Option<Chat>,
+    text: Option<String>,
+    location: Option<Location>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Location {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Serialize)]
+struct GetChatMemberPayload {
+    chat_id: i64,
+    user_id: i64,
+}
+
+#[derive(Serialize)]
+struct ReplyPayload<'a> {
+    chat_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i64>,
+    text: &'a str,
+    parse_mode: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<&'a InlineKeyboardMarkup>,
+}
+
+// A MarkdownV2 reply, optionally with link buttons.
+struct Reply {
+    text: String,
+    keyboard: Option<InlineKeyboardMarkup>,
+}
+
+impl Reply {
+    fn text(s: &str) -> Self {
+        Self {
+            text: telegram::escape_markdown(s),
+            keyboard: None,
+        }
+    }
+
+    fn notification(n: &dyn Notification, s: &dyn NotificationService) -> Self {
+        let message = n.message();
+        let text = telegram::render(&message, s.get_config());
+        // A reply is a single message, so an oversized link is left out rather than split off.
+        let text = telegram::truncate_markdown(&text, telegram::MESSAGE_LIMIT, message.primary_url())
+            .or_else(|| telegram::truncate_markdown(&text, telegram::MESSAGE_LIMIT, None))
+            .unwrap_or_default();
+        Self {
+            text,
+            keyboard: telegram::keyboard(&message, s.get_config()),
+        }
+    }
+}
+
+pub struct Bot {
+    client: Client,
+    api_key: String,
+    monitors: Monitors,
+    status: Registry,
+    subscriptions: Store,
+}
+
+impl Bot {
+    pub fn new(
+        client: Client,
+        api_key: String,
+        monitors: Monitors,
+        status: Registry,
+        subscriptions: Store,
+    ) -> Self {
+        Self {
+            client,
+            api_key,
+            monitors,
+            status,
+            subscriptions,
+        }
+    }
+
+    pub async fn handle(&self, update: Update) {
+        let Some(message) = update.message else {
+            return;
+        };
+        let chat_id = message.chat.id.to_string();
+        let thread_id = message.message_thread_id;
+
+        let reply = match (message.text.as_deref(), &message.location) {
+            (Some(text), _) if text.starts_with('/') => self.command(text, &message).await,
+            // A shared location centres the chat's earthquake area on it. Only in private chats,
+            // since in groups any member sharing their location would change the group's filter.
+            (_, Some(location)) if message.chat.kind == "private" => self.near(&chat_id, thread_id, location.latitude, location.longitude, None).await,
+            _ => return,
+        };
+        let payload = ReplyPayload {
+            chat_id: &chat_id,
+            message_thread_id: message.message_thread_id,
+            text: &reply.text,
+            parse_mode: "MarkdownV2",
+            reply_markup: reply.keyboard.as_ref(),
+        };
+        if let Err(e) = telegram::call(&self.client, &self.api_key, Some(&chat_id), "sendMessage", &payload).await {
+            error!(chat_id = %chat_id, "Failed to reply to bot command: {:?}", e);
+        }
+    }
+
+    async fn command(&self, text: &str, message: &IncomingMessage) -> Reply {
+        let chat_id = &message.chat.id.to_string();
+        let thread_id = message.message_thread_id;
+        let mut words = text.split_whitespace();
+        // In groups commands may be addressed as /command@BotName.
+        let command = words.next().and_then(|c| c.split('@').next()).unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        info!(chat_id, command, "Received bot command.");
+
+        match (command, args.as_slice()) {
+            ("/status", _) => self.status(chat_id).await,
+            ("/latest", [name]) => self.latest(name).await,
+            ("/next", [name]) => self.next(name).await,
+            ("/mute", [duration]) => self.mute(message, duration).await,
+            ("/subscribe", [name]) => self.subscribe(chat_id, thread_id, name, None).await,
+            ("/subscribe", [name, min_score]) => self.subscribe(chat_id, thread_id, name, Some(min_score)).await,
+            ("/unsubscribe", [name]) => self.unsubscribe(chat_id, thread_id, name).await,
+            ("/near", ["off"]) => self.clear_area(chat_id, thread_id).await,
+            ("/near", [latitude, longitude, radius @ ..]) if radius.len() <= 1 => {
+                match (latitude.parse(), longitude.parse(), radius.first().map(|r| r.parse())) {
+                    (Ok(latitude), Ok(longitude), None) => self.near(chat_id, thread_id, latitude, longitude, None).await,
+                    (Ok(latitude), Ok(longitude), Some(Ok(radius))) => {
+                        self.near(chat_id, thread_id, latitude, longitude, Some(radius)).await
+                    }
+                    _ => Reply::text("Usage: /near <latitude> <longitude> [radius in km]"),
+                }
+            }
+            ("/subscriptions", _) => self.list_subscriptions(chat_id, thread_id).await,
+            ("/start" | "/help", _) => Reply::text(HELP),
+            _ => Reply::text(&format!("Unknown command or wrong arguments.\n\n{}", HELP)),
+        }
+    }
+
+    async fn mute(&self, message: &IncomingMessage, duration: &str) -> Reply {
+        if !self.is_admin(message).await {
+            return Reply::text("Only administrators of this chat can mute or resume notifications.");
+        }
+        let chat_id = message.chat.id.to_string();
+        let (until, reply) = if duration == "off" {
+            (None, "Notifications to this chat are resumed.".to_string())
+        } else {
+            let Some(duration) = parse_duration(duration) else {
+                return Reply::text("Usage: /mute 30m, /mute 2h, /mute 1d or /mute off");
+            };
+            let until = Utc::now() + duration;
+            let reply = format!(
+                "Notifications to this chat are muted until {}. Send /mute off to resume.",
+                format_time(until.timestamp())
+            );
+            (Some(until), reply)
+        };
+        if let Err(e) = telegram::mute(&chat_id, until).await {
+            error!(chat_id, "Failed to save muted chats: {:?}", e);
+            return Reply::text(&format!("{} It could not be saved and only lasts until the next restart.", reply));
+        }
+        Reply::text(&reply)
+    }
+
+    // Muting silences a group for every member, so there only its administrators may do it.
+    async fn is_admin(&self, message: &IncomingMessage) -> bool {
+        if message.chat.kind == "private" || message.sender_chat.as_ref().is_some_and(|c| c.id == message.chat.id) {
+            return true;
+        }
+        let Some(user) = &message.from else {
+            return false;
+        };
+        let payload = GetChatMemberPayload {
+            chat_id: message.chat.id,
+            user_id: user.id,
+        };
+        match telegram::call(&self.client, &self.api_key, None, "getChatMember", &payload).await {
+            Ok(member) => matches!(member["status"].as_str(), Some("creator" | "administrator")),
+            Err(e) => {
+                error!(chat_id = message.chat.id, "Failed to look up a chat member: {:?}", e);
+                false
+            }
+        }
+    }
+
+    async fn find(&self, name: &str) -> Option<Arc<Box<dyn NotificationService>>> {
+        let service_name = config::service_name(name)?;
+        self.monitors.services().await.into_iter().find(|s| s.name() == service_name)
+    }
+
+    async fn status(&self, chat_id: &str) -> Reply {
+        let mut sections = Vec::new();
+        for s in self.monitors.services().await {
+            let status = self.status.get(s.name()).await;
+            let last_check = status
+                .last_check
+                .map_or_else(|| "never".to_string(), |t| format_time(t.timestamp()));
+            let last_error = status.last_error.map_or_else(
+                || "none".to_string(),
+                |(t, e)| format!("{} ({})", truncate(&e, 200), format_time(t.timestamp())),
+            );
+            sections.push(format!(
+                "*{}*\n{}",
+                telegram::escape_markdown(s.name()),
+                telegram::escape_markdown(&format!("Last check: {}\nLast error: {}", last_check, last_error))
+            ));
+        }
+        if let Some(until) = telegram::muted_until(chat_id) {
+            sections.push(telegram::escape_markdown(&format!(
+                "This chat is muted until {}.",
+                format_time(until.timestamp())
+            )));
+        }
+
+        Reply {
+            text: sections.join("\n\n"),
+            keyboard: None,
+        }
+    }
+
+    async fn latest(&self, name: &str) -> Reply {
+        let Some(s) = self.find(name).await else {
+            return Reply::text(&format!("Unknown service '{}'.\n\n{}", name, HELP));
+        };
+        match self.status.get(s.name()).await.latest {
+            Some(n) => Reply::notification(n.as_ref(), s.as_ref().as_ref()),
+            None => Reply::text(&format!("No {} notifications since the last restart.", s.name())),
+        }
+    }
+
+    async fn next(&self, name: &str) -> Reply {
+        let Some(s) = self.find(name).await else {
+            return Reply::text(&format!("Unknown service '{}'.\n\n{}", name, HELP));
+        };
+        match s.next_event().await {
+            Ok(Some(n)) => Reply::notification(n.as_ref(), s.as_ref().as_ref()),
+            Ok(None) => Reply::text(&format!("No upcoming {} events known.", s.name())),
+            Err(e) => {
+                error!(service = s.name(), "Failed to fetch the next event: {:?}", e);
+                Reply::text(&format!("Could not fetch the next {} event, please try again later.", s.name()))
+            }
+        }
+    }
+
+    async fn subscribe(&self, chat_id: &str, thread_id: Option<i64>, name: &str, min_score: Option<&str>) -> Reply {
+        let Some(s) = self.find(name).await else {
+            return Reply::text(&format!("Unknown service '{}'.\n\n{}", name, HELP));
+        };
+        if let Some(reply) = configured(s.as_ref().as_ref(), chat_id, thread_id) {
+            return reply;
+        }
+        let min_score = match (min_score, s.score_name()) {
+            (None, _) => None,
+            (Some(min_score), Some(_)) => match min_score.parse::<f64>() {
+                Ok(min_score) if min_score.is_finite() => Some(min_score),
+                _ => return Reply::text(&format!("'{}' is not a number.", min_score)),
+            },
+            (Some(_), None) => {
+                return Reply::text(&format!("{} notifications have no score to filter on.", s.name()));
+            }
+        };
+
+        let existing = self.subscriptions.find(chat_id, thread_id, s.name()).await;
+        let subscription = Subscription {
+            chat_id: chat_id.to_string(),
+            thread_id,
+            service: s.name().to_string(),
+            min_score,
+            area: existing.and_then(|e| e.area),
+        };
+        if let Err(e) = self.subscriptions.subscribe(subscription).await {
+            error!(chat_id, service = s.name(), "Failed to save subscription: {:?}", e);
+            return Reply::text("Could not save the subscription, please try again later.");
+        }
+        match (min_score, s.score_name()) {
+            (Some(min_score), Some(score_name)) => Reply::text(&format!(
+                "Subscribed to {} notifications with a {} of {} or more.",
+                s.name(),
+                score_name,
+                min_score
+            )),
+            _ => Reply::text(&format!("Subscribed to {} notifications.", s.name())),
+        }
+    }
+
+    async fn unsubscribe(&self, chat_id: &str, thread_id: Option<i64>, name: &str) -> Reply {
+        let Some(s) = self.find(name).await else {
+            return Reply::text(&format!("Unknown service '{}'.\n\n{}", name, HELP));
+        };
+        if let Some(reply) = configured(s.as_ref().as_ref(), chat_id, thread_id) {
+            return reply;
+        }
+        match self.subscriptions.unsubscribe(chat_id, thread_id, s.name()).await {
+            Ok(true) => Reply::text(&format!("Unsubscribed from {} notifications.", s.name())),
+            Ok(false) => Reply::text(&format!("This chat is not subscribed to {} notifications.", s.name())),
+            Err(e) => {
+                error!(chat_id, service = s.name(), "Failed to save subscriptions: {:?}", e);
+                Reply::text("Could not remove the subscription, please try again later.")
+            }
+        }
+    }
+
+    async fn list_subscriptions(&self, chat_id: &str, thread_id: Option<i64>) -> Reply {
+        let subscriptions = self.subscriptions.for_chat(chat_id, thread_id).await;
+        if subscriptions.is_empty() {
+            return Reply::text("This chat has no subscriptions. Use /subscribe to add one.");
+        }
+
+        let services = self.monitors.services().await;
+        let lines: Vec<String> = subscriptions
+            .iter()
+            .map(|sub| {
+                let score_name = services
+                    .iter()
+                    .find(|s| s.name() == sub.service)
+                    .and_then(|s| s.score_name())
+                    .unwrap_or("score");
+                let mut filters = Vec::new();
+                if let Some(min_score) = sub.min_score {
+                    filters.push(format!("{} {}+", score_name, min_score));
+                }
+                if let Some(area) = &sub.area {
+                    filters.push(format!(
+                        "within {} km of {:.4}, {:.4}",
+                        area.radius_km, area.latitude, area.longitude
+                    ));
+                }
+                if filters.is_empty() {
+                    format!("- {}", sub.service)
+                } else {
+                    format!("- {} ({})", sub.service, filters.join(", "))
+                }
+            })
+            .collect();
+        Reply::text(&format!("Subscriptions of this chat:\n{}", lines.join("\n")))
+    }
+
+    // Subscribes the chat to earthquakes around a point, keeping its minimum magnitude and
+    // previous radius.
+    async fn near(&self, chat_id: &str, thread_id: Option<i64>, latitude: f64, longitude: f64, radius_km: Option<f64>) -> Reply {
+        let Some(s) = self.find("quake").await else {
+            return Reply::text("The earthquake service is not enabled.");
+        };
+        if let Some(reply) = configured(s.as_ref().as_ref(), chat_id, thread_id) {
+            return reply;
+        }
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Reply::text("Latitude must be between -90 and 90 and longitude between -180 and 180.");
+        }
+        if radius_km.is_some_and(|r| !(r > 0.0 && r <= 20_000.0)) {
+            return Reply::text("The radius must be between 0 and 20000 km.");
+        }
+
+        let existing = self.subscriptions.find(chat_id, thread_id, s.name()).await;
+        let radius_km = radius_km
+            .or_else(|| existing.as_ref().and_then(|e| e.area).map(|a| a.radius_km))
+            .unwrap_or(DEFAULT_RADIUS_KM);
+        let subscription = Subscription {
+            chat_id: chat_id.to_string(),
+            thread_id,
+            service: s.name().to_string(),
+            min_score: existing.and_then(|e| e.min_score),
+            area: Some(Area {
+                latitude,
+                longitude,
+                radius_km,
+            }),
+        };
+        if let Err(e) = self.subscriptions.subscribe(subscription).await {
+            error!(chat_id, service = s.name(), "Failed to save subscription: {:?}", e);
+            return Reply::text("Could not save the subscription, please try again later.");
+        }
+        Reply::text(&format!(
+            "Subscribed to earthquakes within {} km of {:.4}, {:.4}. Send /near <latitude> <longitude> <radius> to change the radius.",
+            radius_km, latitude, longitude
+        ))
+    }
+
+    async fn clear_area(&self, chat_id: &str, thread_id: Option<i64>) -> Reply {
+        let Some(s) = self.find("quake").await else {
+            return Reply::text("The earthquake service is not enabled.");
+        };
+        let Some(mut subscription) = self
+            .subscriptions
+            .find(chat_id, thread_id, s.name())
+            .await
+            .filter(|sub| sub.area.is_some())
+        else {
+            return Reply::text("This chat has no earthquake location filter.");
+        };
+
+        subscription.area = None;
+        if let Err(e) = self.subscriptions.subscribe(subscription).await {
+            error!(chat_id, service = s.name(), "Failed to save subscription: {:?}", e);
+            return Reply::text("Could not save the subscription, please try again later.");
+        }
+        Reply::text("Removed the location filter, this chat now receives earthquakes everywhere.")
+    }
+}
+
+// Chats configured for a service receive all of its notifications, so subscriptions and their
+// filters would have no effect there.
+fn configured(s: &dyn NotificationService, chat_id: &str, thread_id: Option<i64>) -> Option<Reply> {
+    let configured = s
+        .get_config()
+        .telegram_chats
+        .iter()
+        .any(|c| c.chat_id == chat_id && c.thread_id == thread_id);
+    configured.then(|| {
+        Reply::text(&format!(
+            "This chat receives every {} notification through the configuration, so subscriptions and filters do not apply here.",
+            s.name()
+        ))
+    })
+}
+
+
+// Parses durations such as `30m`, `2h` or `1d`.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (value, unit_secs) = if let Some(value) = s.strip_suffix('m') {
+        (value, 60)
+    } else if let Some(value) = s.strip_suffix('h') {
+        (value, 3600)
+    } else if let Some(value) = s.strip_suffix('d') {
+        (value, 24 * 3600)
+    } else {
+        return None;
+    };
+    let value: u64 = value.parse().ok().filter(|v| *v > 0)?;
+    Some(Duration::from_secs(value.checked_mul(unit_secs)?))
+}
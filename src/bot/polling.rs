@@ -0,0 +1,96 @@
+use super::{Bot, Update};
+use crate::channels::telegram;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time;
+use tracing::{error, info};
+
+const POLL_TIMEOUT_SECS: u64 = 50;
+// How long shutting down waits to confirm the handled updates.
+const ACKNOWLEDGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct GetUpdatesPayload<'a> {
+    offset: i64,
+    timeout: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+    allowed_updates: &'a [&'a str],
+}
+
+#[derive(Serialize)]
+struct DeleteWebhookPayload {
+    drop_pending_updates: bool,
+}
+
+// Polls until `shutdown` changes, then confirms the updates handled so far.
+pub async fn run(bot: Arc<Bot>, mut shutdown: watch::Receiver<bool>) {
+    // getUpdates is refused with 409 Conflict while a webhook is set, e.g. after switching
+    // back from webhook mode.
+    let payload = DeleteWebhookPayload {
+        drop_pending_updates: false,
+    };
+    tokio::select! {
+        result = telegram::call(&bot.client, &bot.api_key, None, "deleteWebhook", &payload) => {
+            if let Err(e) = result {
+                error!("Could not remove the Telegram webhook: {:?}", e);
+            }
+        }
+        _ = shutdown.changed() => return,
+    }
+
+    info!("Polling Telegram for bot commands");
+    let mut offset = 0;
+    loop {
+        let payload = GetUpdatesPayload {
+            offset,
+            timeout: POLL_TIMEOUT_SECS,
+            limit: None,
+            allowed_updates: &["message"],
+        };
+        let updates = tokio::select! {
+            result = telegram::call(&bot.client, &bot.api_key, None, "getUpdates", &payload) => {
+                result.and_then(|result| Ok(serde_json::from_value::<Vec<Update>>(result)?))
+            }
+            _ = shutdown.changed() => break,
+        };
+        let updates = match updates {
+            Ok(updates) => updates,
+            Err(e) => {
+                error!("Failed to fetch bot updates: {:?}", e);
+                tokio::select! {
+                    _ = time::sleep(Duration::from_secs(5)) => continue,
+                    _ = shutdown.changed() => break,
+                }
+            }
+        };
+
+        for update in updates {
+            offset = update.update_id + 1;
+            bot.handle(update).await;
+        }
+    }
+    acknowledge(&bot, offset).await;
+}
+
+// Telegram only forgets updates once getUpdates is called with a later offset, so without
+// this the last commands would be handled again after a restart.
+async fn acknowledge(bot: &Bot, offset: i64) {
+    if offset == 0 {
+        return;
+    }
+    let payload = GetUpdatesPayload {
+        offset,
+        timeout: 0,
+        limit: Some(1),
+        allowed_updates: &["message"],
+    };
+    let call = telegram::call(&bot.client, &bot.api_key, None, "getUpdates", &payload);
+    match time::timeout(ACKNOWLEDGE_TIMEOUT, call).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => error!("Could not confirm the handled bot updates: {:?}", e),
+        Err(_) => error!("Timed out confirming the handled bot updates"),
+    }
+}
@@ -0,0 +1,74 @@
+use super::{Bot, Update};
+use crate::channels::telegram;
+use crate::config::Secret;
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+const SECRET_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+#[derive(Clone)]
+struct WebhookState {
+    bot: Arc<Bot>,
+    secret: Secret,
+}
+
+#[derive(Serialize)]
+struct SetWebhookPayload<'a> {
+    url: &'a str,
+    secret_token: &'a str,
+    allowed_updates: &'a [&'a str],
+}
+
+pub fn router(bot: Arc<Bot>, secret: Secret) -> Router {
+    Router::new()
+        .route("/telegram/webhook/:secret", post(receive))
+        .with_state(WebhookState { bot, secret })
+}
+
+// The body is only parsed once the request is authenticated.
+async fn receive(
+    State(state): State<WebhookState>,
+    Path(secret): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    if !state.secret.matches(&secret) {
+        return StatusCode::NOT_FOUND;
+    }
+    let token = headers.get(SECRET_HEADER).and_then(|v| v.to_str().ok());
+    if !token.is_some_and(|token| state.secret.matches(token)) {
+        warn!("Rejected Telegram webhook request without a valid secret token.");
+        return StatusCode::UNAUTHORIZED;
+    }
+    let update: Update = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            warn!("Rejected Telegram webhook request with an invalid update: {}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    // Answer right away so Telegram does not redeliver the update while the reply is being sent.
+    tokio::spawn(async move { state.bot.handle(update).await });
+    StatusCode::OK
+}
+
+pub async fn register(bot: &Bot, base_url: &str, secret: &str) -> anyhow::Result<()> {
+    let url = format!("{}/telegram/webhook/{}", base_url.trim_end_matches('/'), secret);
+    let payload = SetWebhookPayload {
+        url: &url,
+        secret_token: secret,
+        allowed_updates: &["message"],
+    };
+    telegram::call(&bot.client, &bot.api_key, None, "setWebhook", &payload).await?;
+    info!("Registered Telegram webhook at {}/telegram/webhook/...", base_url.trim_end_matches('/'));
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use super::{plain_text, truncate, Capabilities, Notifier};
+use crate::{
+    config::{DiscordConfig, ServiceConfig},
+    services::{Notification, Severity},
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::error;
+
+const SUPPRESS_NOTIFICATIONS: u32 = 1 << 12;
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    username: &'a str,
+    embeds: Vec<Embed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flags: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Embed {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    color: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    fields: Vec<EmbedField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    footer: Option<EmbedFooter>,
+}
+
+#[derive(Serialize)]
+struct EmbedField {
+    name: String,
+    value: String,
+    inline: bool,
+}
+
+#[derive(Serialize)]
+struct EmbedFooter {
+    text: String,
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    config: DiscordConfig,
+}
+
+impl Sender {
+    pub fn new(client: Client, config: DiscordConfig) -> Self {
+        Self { client, config }
+    }
+
+    fn color(severity: Severity) -> u32 {
+        match severity {
+            Severity::Info => 0x3498db,
+            Severity::Minor => 0xf1c40f,
+            Severity::Major => 0xe67e22,
+            Severity::Critical => 0xe74c3c,
+        }
+    }
+
+    async fn execute(&self, n: &dyn Notification, cfg: &ServiceConfig, silent: bool) -> anyhow::Result<()> {
+        let message = n.message();
+
+        let mut fields: Vec<EmbedField> = message
+            .fields
+            .iter()
+            .map(|f| {
+                let value = f.value.to_string();
+                EmbedField {
+                    inline: value.chars().count() <= 40,
+                    name: truncate(&f.name, 256),
+                    value: truncate(&value, 1024),
+                }
+            })
+            .collect();
+        let links = message.display_links();
+        if links.len() > 1 {
+            let links: Vec<String> = links.iter().map(|l| format!("[{}]({})", l.label, l.url)).collect();
+            fields.push(EmbedField {
+                name: "Links".to_string(),
+                value: truncate(&links.join(" | "), 1024),
+                inline: false,
+            });
+        }
+        if !cfg.buymeacoffee_url.is_empty() {
+            fields.push(EmbedField {
+                name: "Like this service?".to_string(),
+                value: format!("[Buy Me a Coffee ☕]({})", cfg.buymeacoffee_url),
+                inline: false,
+            });
+        }
+
+        let embed = Embed {
+            title: truncate(&message.heading(), 256),
+            url: message.primary_url().map(str::to_string),
+            color: Self::color(message.severity),
+            timestamp: chrono::DateTime::from_timestamp(n.get_timestamp(), 0).map(|t| t.to_rfc3339()),
+            fields,
+            footer: (!cfg.disclaimer.is_empty()).then(|| EmbedFooter {
+                text: truncate(&plain_text(&cfg.disclaimer), 2048),
+            }),
+        };
+        let payload = WebhookPayload {
+            username: "Automatex Notifier",
+            embeds: vec![embed],
+            flags: silent.then_some(SUPPRESS_NOTIFICATIONS),
+        };
+
+        // The webhook URL works as a credential, so errors leave it out.
+        let res = self.client.post(self.config.webhook_url.expose()).json(&payload).send().await.map_err(reqwest::Error::without_url)?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_else(|_| "Could not read body".to_string());
+            error!(status = %status, body = %body, "Discord webhook returned non-200 status");
+            return Err(anyhow::anyhow!("Discord webhook error: {}", status));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "Discord" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            // Discord allows 30 requests per minute per webhook.
+            send_interval: Duration::from_secs(2),
+            ..Default::default()
+        }
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.execute(n, cfg, false).await
+    }
+
+    async fn send_silent(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.execute(n, cfg, true).await
+    }
+}
@@ -0,0 +1,102 @@
+use super::{render, Capabilities, Notifier};
+use crate::{
+    config::{EmailConfig, ServiceConfig, SmtpTls},
+    services::{Message, Notification},
+};
+use async_trait::async_trait;
+use lettre::{
+    message::MultiPart,
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+};
+
+#[derive(Clone)]
+pub struct Sender {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    config: EmailConfig,
+}
+
+impl Sender {
+    pub fn new(config: EmailConfig) -> anyhow::Result<Self> {
+        if config.to.is_empty() {
+            return Err(anyhow::anyhow!("EMAIL_TO has no recipients"));
+        }
+
+        let mut builder = match config.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)?,
+        }
+        .port(config.smtp_port);
+        if !config.smtp_username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.smtp_username.clone(),
+                config.smtp_password.expose().to_string(),
+            ));
+        }
+
+        Ok(Self {
+            mailer: builder.build(),
+            config,
+        })
+    }
+
+    async fn deliver(&self, subject: &str, text: String, html: String) -> anyhow::Result<()> {
+        let mut builder = lettre::Message::builder()
+            .from(self.config.from.clone())
+            .subject(subject);
+        for to in &self.config.to {
+            builder = builder.to(to.clone());
+        }
+        let email = builder.multipart(MultiPart::alternative_plain_html(text, html))?;
+
+        self.mailer.send(email).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "Email" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            batch: self.config.batch,
+            ..Default::default()
+        }
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        let message = n.message();
+        let (footer_text, footer_html) = render::footer(cfg);
+
+        self.deliver(
+            &message.heading(),
+            render::text(&message) + &footer_text,
+            render::html(&message) + &footer_html,
+        )
+        .await
+    }
+
+    async fn send_batch(&self, ns: &[&dyn Notification], cfg: &ServiceConfig) -> anyhow::Result<()> {
+        let messages: Vec<Message> = ns.iter().map(|n| n.message()).collect();
+        let subject = match messages.as_slice() {
+            [message] => message.heading(),
+            _ => format!("{} new notifications from Automatex Notifier", messages.len()),
+        };
+
+        let mut text = String::new();
+        let mut html = String::new();
+        for message in &messages {
+            if !text.is_empty() {
+                text.push_str("\n====================\n\n");
+                html.push_str("<hr>\n");
+            }
+            text.push_str(&render::text(message));
+            html.push_str(&render::html(message));
+        }
+        let (footer_text, footer_html) = render::footer(cfg);
+
+        self.deliver(&subject, text + &footer_text, html + &footer_html).await
+    }
+}
@@ -0,0 +1,74 @@
+use super::{plain_text, render, Capabilities, Notifier};
+use crate::{
+    config::{GotifyConfig, ServiceConfig},
+    services::{Notification, Severity},
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use tracing::error;
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    config: GotifyConfig,
+}
+
+impl Sender {
+    pub fn new(client: Client, config: GotifyConfig) -> Self {
+        Self { client, config }
+    }
+
+    fn priority(severity: Severity) -> u8 {
+        match severity {
+            Severity::Info => 2,
+            Severity::Minor => 5,
+            Severity::Major => 7,
+            Severity::Critical => 10,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "Gotify" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        let message = n.message();
+        let mut body = render::fields(&message);
+        if !cfg.disclaimer.is_empty() {
+            body.push('\n');
+            body.push_str(&plain_text(&cfg.disclaimer));
+        }
+
+        let mut payload = json!({
+            "title": message.heading(),
+            "message": body,
+            "priority": Self::priority(message.severity),
+        });
+        if let Some(url) = message.primary_url() {
+            payload["extras"] = json!({ "client::notification": { "click": { "url": url } } });
+        }
+
+        let url = format!("{}/message", self.config.server_url.trim_end_matches('/'));
+        let res = self
+            .client
+            .post(&url)
+            .header("X-Gotify-Key", self.config.token.expose())
+            .json(&payload)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_else(|_| "Could not read body".to_string());
+            error!(status = %status, body = %body, "Gotify server returned non-200 status");
+            return Err(anyhow::anyhow!("Gotify error: {}", status));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use super::{render, Capabilities, Notifier};
+use crate::{
+    config::{MatrixConfig, ServiceConfig},
+    services::Notification,
+};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+#[derive(Serialize)]
+struct RoomMessage<'a> {
+    msgtype: &'a str,
+    body: &'a str,
+    format: &'a str,
+    formatted_body: &'a str,
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    config: MatrixConfig,
+}
+
+impl Sender {
+    pub fn new(client: Client, config: MatrixConfig) -> Self {
+        Self { client, config }
+    }
+
+    fn send_url(&self, txn_id: &str) -> anyhow::Result<Url> {
+        let mut url = Url::parse(&self.config.homeserver_url)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid Matrix homeserver URL"))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", &self.config.room_id, "send", "m.room.message", txn_id]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "Matrix" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            send_interval: Duration::from_millis(500),
+            ..Default::default()
+        }
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        let message = n.message();
+        let (footer_text, footer_html) = render::footer(cfg);
+        let body = render::text(&message) + &footer_text;
+        let formatted_body = render::html(&message) + &footer_html;
+
+        // Transaction IDs make the PUT idempotent, so they only need to be unique per access token.
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+        let txn_id = format!("{}-{}", n.get_unique_id(), nanos);
+
+        let payload = RoomMessage {
+            msgtype: "m.text",
+            body: &body,
+            format: "org.matrix.custom.html",
+            formatted_body: &formatted_body,
+        };
+
+        let res = self
+            .client
+            .put(self.send_url(&txn_id)?)
+            .bearer_auth(self.config.access_token.expose())
+            .json(&payload)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_else(|_| "Could not read body".to_string());
+            error!(status = %status, body = %body, "Matrix homeserver returned non-200 status");
+            return Err(anyhow::anyhow!("Matrix API error: {}", status));
+        }
+        Ok(())
+    }
+}
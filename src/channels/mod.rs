@@ -0,0 +1,696 @@
+use crate::config::{ChannelKind, Config, QuietHours, QuietMode, ServiceConfig, TelegramChat};
+use crate::services::{Message, Notification, Severity};
+use crate::state::Manager;
+use crate::subscriptions;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info, warn};
+
+pub mod discord;
+pub mod email;
+pub mod gotify;
+pub mod matrix;
+pub mod ntfy;
+pub mod ratelimit;
+pub mod render;
+pub mod slack;
+pub mod telegram;
+pub mod webhook;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Capabilities {
+    // Pause the dispatcher keeps between consecutive messages to stay under the sink's rate limit.
+    pub send_interval: Duration,
+    // The channel prefers to receive all notifications of a check in one `send_batch` call.
+    pub batch: bool,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+    fn capabilities(&self) -> Capabilities;
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()>;
+
+    // Used during quiet hours; channels without a silent delivery option send normally.
+    async fn send_silent(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.send(n, cfg).await
+    }
+
+    async fn send_batch(&self, ns: &[&dyn Notification], cfg: &ServiceConfig) -> anyhow::Result<()> {
+        for n in ns {
+            self.send(*n, cfg).await?;
+        }
+        Ok(())
+    }
+}
+
+// A notification held for quiet hours as saved to disk, so it is still delivered after a restart.
+#[derive(Debug, Serialize, Deserialize)]
+struct Held {
+    id: String,
+    timestamp: i64,
+    severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+    message: Message,
+}
+
+impl Held {
+    fn of(n: &dyn Notification) -> Self {
+        Self {
+            id: n.get_unique_id().to_string(),
+            timestamp: n.get_timestamp(),
+            severity: n.severity(),
+            score: n.score(),
+            message: n.message(),
+        }
+    }
+}
+
+impl Notification for Held {
+    fn get_unique_id(&self) -> &str { &self.id }
+    fn get_timestamp(&self) -> i64 { self.timestamp }
+    fn message(&self) -> Message { self.message.clone() }
+    fn severity(&self) -> Severity { self.severity }
+    fn score(&self) -> Option<f64> { self.score }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct HeldFile {
+    // By channel name, e.g. "Discord" or "Telegram (-100123)".
+    #[serde(default)]
+    routes: HashMap<String, Vec<Held>>,
+    #[serde(default)]
+    subscribers: Vec<HeldForSubscriber>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeldForSubscriber {
+    chat_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thread_id: Option<i64>,
+    notifications: Vec<Held>,
+}
+
+fn restored(held: Vec<Held>) -> impl Iterator<Item = Arc<dyn Notification>> {
+    held.into_iter().map(|n| Arc::new(n) as Arc<dyn Notification>)
+}
+
+// A notification to send now; `released` ones were held for quiet hours and stay saved until
+// they are delivered.
+struct Delivery {
+    n: Arc<dyn Notification>,
+    silent: bool,
+    released: bool,
+}
+
+#[derive(Clone)]
+struct Route {
+    notifier: Arc<dyn Notifier>,
+    min_severity: Severity,
+    quiet_hours: Option<QuietHours>,
+    // Notifications kept back until the route's quiet hours end.
+    held: Arc<Mutex<Vec<Arc<dyn Notification>>>>,
+}
+
+impl Route {
+    fn new(notifier: Arc<dyn Notifier>, min_severity: Severity, quiet_hours: Option<QuietHours>) -> Self {
+        Self {
+            notifier,
+            min_severity,
+            quiet_hours,
+            held: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn accepts(&self, n: &dyn Notification) -> bool {
+        n.severity() >= self.min_severity
+    }
+
+    // Returns the notifications to deliver now and holds back the ones that have to wait for
+    // quiet hours to end. Released notifications are only dropped once `delivered`.
+    fn schedule(&self, ns: &[Arc<dyn Notification>], now: DateTime<Utc>) -> Vec<Delivery> {
+        let quiet = self.quiet_hours.as_ref().filter(|q| q.is_active(now));
+        let holding = quiet.is_some_and(|q| q.mode == QuietMode::Hold);
+        let mut held = self.held.lock().unwrap();
+
+        let mut deliveries: Vec<_> = if holding {
+            Vec::new()
+        } else {
+            held.iter()
+                .map(|n| Delivery {
+                    n: n.clone(),
+                    silent: false,
+                    released: true,
+                })
+                .collect()
+        };
+        for n in ns.iter().filter(|n| self.accepts(n.as_ref())) {
+            let silent = match quiet {
+                Some(q) if q.applies_to(n.severity()) => match q.mode {
+                    QuietMode::Silent => true,
+                    QuietMode::Hold => {
+                        info!(channel = self.notifier.name(), notification_id = %n.get_unique_id(), "Holding notification until quiet hours end.");
+                        held.push(n.clone());
+                        continue;
+                    }
+                },
+                _ => false,
+            };
+            deliveries.push(Delivery {
+                n: n.clone(),
+                silent,
+                released: false,
+            });
+        }
+        deliveries
+    }
+
+    fn delivered(&self, delivery: &Delivery) {
+        if delivery.released {
+            self.held.lock().unwrap().retain(|n| !Arc::ptr_eq(n, &delivery.n));
+        }
+    }
+}
+
+// Telegram routes for chats that subscribed through the bot. Routes are kept while the chat is
+// subscribed, so notifications held during quiet hours survive between checks.
+struct Subscribers {
+    service_name: String,
+    store: subscriptions::Store,
+    client: reqwest::Client,
+    api_key: String,
+    state: Manager,
+    quiet_hours: Option<QuietHours>,
+    // Chats that already receive this service through the configuration.
+    configured: Vec<TelegramChat>,
+    routes: Mutex<HashMap<(String, Option<i64>), Route>>,
+}
+
+impl Subscribers {
+    fn route(&self, chat_id: &str, thread_id: Option<i64>) -> Route {
+        let chat = TelegramChat {
+            chat_id: chat_id.to_string(),
+            thread_id,
+            min_severity: Severity::Info,
+        };
+        Route::new(
+            Arc::new(telegram::Sender::new(
+                self.client.clone(),
+                self.api_key.clone(),
+                chat,
+                self.state.clone(),
+            )),
+            Severity::Info,
+            self.quiet_hours.clone(),
+        )
+    }
+
+    fn is_configured(&self, s: &subscriptions::Subscription) -> bool {
+        self.configured.iter().any(|c| c.chat_id == s.chat_id && c.thread_id == s.thread_id)
+    }
+
+    // Returns each subscriber's route with the notifications matching its preferences.
+    async fn targets(&self, ns: &[Arc<dyn Notification>]) -> Vec<(Route, Vec<Arc<dyn Notification>>)> {
+        let subscriptions = self.store.for_service(&self.service_name).await;
+        let mut routes = self.routes.lock().unwrap();
+        subscriptions
+            .into_iter()
+            .filter(|s| !self.is_configured(s))
+            .map(|s| {
+                let route = routes
+                    .entry((s.chat_id.clone(), s.thread_id))
+                    .or_insert_with(|| self.route(&s.chat_id, s.thread_id))
+                    .clone();
+                let accepted = ns.iter().filter_map(|n| s.matching(n)).collect();
+                (route, accepted)
+            })
+            .collect()
+    }
+}
+
+pub struct Dispatcher {
+    routes: Vec<Route>,
+    subscribers: Subscribers,
+    // Where notifications held for quiet hours are kept, next to the state files.
+    held_path: PathBuf,
+}
+
+impl Dispatcher {
+    pub fn for_service(
+        config: &Config,
+        service_name: &str,
+        cfg: &ServiceConfig,
+        state: &Manager,
+        subscriptions: &subscriptions::Store,
+        client: &reqwest::Client,
+    ) -> Self {
+        let mut routes: Vec<Route> = cfg
+            .telegram_chats
+            .iter()
+            .map(|chat| {
+                Route::new(
+                    Arc::new(telegram::Sender::new(
+                        client.clone(),
+                        cfg.telegram_api_key.expose().to_string(),
+                        chat.clone(),
+                        state.clone(),
+                    )),
+                    chat.min_severity,
+                    cfg.telegram_quiet_hours.clone().or_else(|| cfg.quiet_hours.clone()),
+                )
+            })
+            .collect();
+        for channel in config.channels.iter().filter(|c| c.accepts_service(service_name)) {
+            let notifier = match notifier(&channel.kind, client, service_name) {
+                Ok(notifier) => notifier,
+                Err(e) => {
+                    error!("Could not set up {} channel: {:?}", channel.kind.name(), e);
+                    continue;
+                }
+            };
+            routes.push(Route::new(
+                notifier,
+                channel.min_severity,
+                channel.quiet_hours.clone().or_else(|| cfg.quiet_hours.clone()),
+            ));
+        }
+        let subscribers = Subscribers {
+            service_name: service_name.to_string(),
+            store: subscriptions.clone(),
+            client: client.clone(),
+            api_key: cfg.telegram_api_key.expose().to_string(),
+            state: state.clone(),
+            quiet_hours: cfg.telegram_quiet_hours.clone().or_else(|| cfg.quiet_hours.clone()),
+            configured: cfg.telegram_chats.clone(),
+            routes: Mutex::new(HashMap::new()),
+        };
+        let held_path = PathBuf::from(format!("held_{}.json", service_name.to_lowercase().replace(' ', "_")));
+        Self {
+            routes,
+            subscribers,
+            held_path,
+        }
+    }
+
+    // Restores the notifications that were held when the application last stopped.
+    pub async fn load_held(&self) -> anyhow::Result<()> {
+        let data = match tokio::fs::read(&self.held_path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let file: HeldFile = serde_json::from_slice(&data)?;
+
+        for (name, held) in file.routes {
+            match self.routes.iter().find(|r| r.notifier.name() == name) {
+                Some(route) => route.held.lock().unwrap().extend(restored(held)),
+                None => warn!(channel = %name, count = held.len(), "Dropping held notifications of a removed channel."),
+            }
+        }
+        let mut routes = self.subscribers.routes.lock().unwrap();
+        for subscriber in file.subscribers {
+            routes
+                .entry((subscriber.chat_id.clone(), subscriber.thread_id))
+                .or_insert_with(|| self.subscribers.route(&subscriber.chat_id, subscriber.thread_id))
+                .held
+                .lock()
+                .unwrap()
+                .extend(restored(subscriber.notifications));
+        }
+        info!("[{}] Restored held notifications.", self.held_path.display());
+        Ok(())
+    }
+
+    // Removes the file once nothing is held, so it only exists while quiet hours hold something.
+    // Notifications held for chats that have since unsubscribed are dropped.
+    async fn save_held(&self) -> anyhow::Result<()> {
+        let subscribed: Vec<_> = self
+            .subscribers
+            .store
+            .for_service(&self.subscribers.service_name)
+            .await
+            .into_iter()
+            .filter(|s| !self.subscribers.is_configured(s))
+            .map(|s| (s.chat_id, s.thread_id))
+            .collect();
+        self.subscribers.routes.lock().unwrap().retain(|key, route| {
+            let keep = subscribed.contains(key);
+            if !keep && !route.held.lock().unwrap().is_empty() {
+                warn!(chat_id = %key.0, "Dropping held notifications of a chat that unsubscribed.");
+            }
+            keep
+        });
+
+        let mut file = HeldFile::default();
+        for route in &self.routes {
+            let held = route.held.lock().unwrap();
+            if !held.is_empty() {
+                file.routes
+                    .insert(route.notifier.name().to_string(), held.iter().map(|n| Held::of(n.as_ref())).collect());
+            }
+        }
+        for ((chat_id, thread_id), route) in self.subscribers.routes.lock().unwrap().iter() {
+            let held = route.held.lock().unwrap();
+            if !held.is_empty() {
+                file.subscribers.push(HeldForSubscriber {
+                    chat_id: chat_id.clone(),
+                    thread_id: *thread_id,
+                    notifications: held.iter().map(|n| Held::of(n.as_ref())).collect(),
+                });
+            }
+        }
+
+        if file.routes.is_empty() && file.subscribers.is_empty() {
+            return match tokio::fs::remove_file(&self.held_path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        tokio::fs::write(&self.held_path, serde_json::to_vec_pretty(&file)?).await?;
+        Ok(())
+    }
+
+    // Takes over the notifications a previous dispatcher of the service holds for quiet hours,
+    // so reloading the configuration does not lose them.
+    pub fn adopt_held(&self, previous: &Dispatcher) {
+        for old in &previous.routes {
+            let held: Vec<_> = old.held.lock().unwrap().drain(..).collect();
+            if held.is_empty() {
+                continue;
+            }
+            match self.routes.iter().find(|r| r.notifier.name() == old.notifier.name()) {
+                Some(route) => route.held.lock().unwrap().extend(held),
+                None => warn!(channel = old.notifier.name(), count = held.len(), "Dropping held notifications of a removed channel."),
+            }
+        }
+
+        let old_routes = previous.subscribers.routes.lock().unwrap();
+        let mut routes = self.subscribers.routes.lock().unwrap();
+        for ((chat_id, thread_id), old) in old_routes.iter() {
+            let held: Vec<_> = old.held.lock().unwrap().drain(..).collect();
+            if held.is_empty() {
+                continue;
+            }
+            routes
+                .entry((chat_id.clone(), *thread_id))
+                .or_insert_with(|| self.subscribers.route(chat_id, *thread_id))
+                .held
+                .lock()
+                .unwrap()
+                .extend(held);
+        }
+    }
+
+    // Also releases notifications held during quiet hours, so it is called after every check
+    // even when nothing new was found.
+    pub async fn dispatch(&self, ns: &[Arc<dyn Notification>], cfg: &ServiceConfig) {
+        let now = Utc::now();
+        let mut targets: Vec<(Route, Vec<Arc<dyn Notification>>)> =
+            self.routes.iter().map(|r| (r.clone(), ns.to_vec())).collect();
+        targets.extend(self.subscribers.targets(ns).await);
+
+        let scheduled: Vec<_> = targets
+            .iter()
+            .map(|(route, ns)| (route, route.schedule(ns, now)))
+            .filter(|(_, deliveries)| !deliveries.is_empty())
+            .collect();
+        // Saved before sending, so neither newly held nor released notifications are lost if
+        // the application stops halfway; released ones are removed again once delivered.
+        if let Err(e) = self.save_held().await {
+            error!("Error saving held notifications: {:?}", e);
+        }
+        let released = scheduled.iter().any(|(_, deliveries)| deliveries.iter().any(|d| d.released));
+
+        let mut single = Vec::new();
+        for (route, deliveries) in scheduled {
+            let notifier = &route.notifier;
+            if !notifier.capabilities().batch {
+                single.push((route, deliveries));
+                continue;
+            }
+
+            let batch: Vec<&dyn Notification> = deliveries.iter().map(|d| d.n.as_ref()).collect();
+            if let Err(e) = notifier.send_batch(&batch, cfg).await {
+                error!(channel = notifier.name(), count = batch.len(), "Failed to send notification batch: {:?}", e);
+            } else {
+                info!(channel = notifier.name(), count = batch.len(), "Successfully sent notification batch.");
+                deliveries.iter().for_each(|d| route.delivered(d));
+            }
+        }
+
+        let send_interval = single
+            .iter()
+            .map(|(route, _)| route.notifier.capabilities().send_interval)
+            .max()
+            .unwrap_or_default();
+        let rounds = single.iter().map(|(_, deliveries)| deliveries.len()).max().unwrap_or_default();
+        for i in 0..rounds {
+            for (route, deliveries) in &single {
+                let Some(delivery) = deliveries.get(i) else {
+                    continue;
+                };
+                let (notifier, n) = (&route.notifier, &delivery.n);
+                let result = if delivery.silent {
+                    notifier.send_silent(n.as_ref(), cfg).await
+                } else {
+                    notifier.send(n.as_ref(), cfg).await
+                };
+                if let Err(e) = result {
+                    error!(channel = notifier.name(), notification_id = %n.get_unique_id(), "Failed to send notification: {:?}", e);
+                } else {
+                    info!(channel = notifier.name(), notification_id = %n.get_unique_id(), "Successfully sent notification.");
+                    route.delivered(delivery);
+                }
+            }
+            time::sleep(send_interval).await;
+        }
+
+        // Released notifications that failed are still held and retried after the next check.
+        if released && let Err(e) = self.save_held().await {
+            error!("Error saving held notifications: {:?}", e);
+        }
+    }
+}
+
+pub fn notifier(kind: &ChannelKind, client: &reqwest::Client, service_name: &str) -> anyhow::Result<Arc<dyn Notifier>> {
+    Ok(match kind {
+        ChannelKind::Discord(c) => Arc::new(discord::Sender::new(client.clone(), c.clone())),
+        ChannelKind::Slack(c) => Arc::new(slack::Sender::new(client.clone(), c.clone())),
+        ChannelKind::Matrix(c) => Arc::new(matrix::Sender::new(client.clone(), c.clone())),
+        ChannelKind::Webhook(c) => Arc::new(webhook::Sender::new(client.clone(), c.clone(), service_name)),
+        ChannelKind::Ntfy(c) => Arc::new(ntfy::Sender::new(client.clone(), c.clone())),
+        ChannelKind::Gotify(c) => Arc::new(gotify::Sender::new(client.clone(), c.clone())),
+        ChannelKind::Email(c) => Arc::new(email::Sender::new(c.clone())?),
+    })
+}
+
+// Strips Telegram MarkdownV2 escapes and emphasis markers from config-provided text
+// (e.g. disclaimers) so it can be shown on channels with other markup rules.
+pub fn plain_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            '*' | '_' | '~' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LongMessageMode;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct Recorder {
+        failing: AtomicBool,
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            "Recorder"
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+        }
+
+        async fn send(&self, n: &dyn Notification, _cfg: &ServiceConfig) -> anyhow::Result<()> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(anyhow::anyhow!("unreachable"));
+            }
+            self.sent.lock().unwrap().push(n.get_unique_id().to_string());
+            Ok(())
+        }
+    }
+
+    fn notification(id: &str, severity: Severity, score: Option<f64>) -> Arc<dyn Notification> {
+        Arc::new(Held {
+            id: id.to_string(),
+            timestamp: 0,
+            severity,
+            score,
+            message: Message {
+                title: id.to_string(),
+                severity,
+                fields: Vec::new(),
+                links: Vec::new(),
+                geo: None,
+                tags: Vec::new(),
+                updated: false,
+            },
+        })
+    }
+
+    pub(crate) fn service_config() -> ServiceConfig {
+        ServiceConfig {
+            enabled: true,
+            check_interval: Duration::from_secs(60),
+            telegram_api_key: Default::default(),
+            telegram_chats: Vec::new(),
+            telegram_long_messages: LongMessageMode::Split,
+            telegram_send_location: false,
+            telegram_quiet_hours: None,
+            quiet_hours: None,
+            buymeacoffee_url: String::new(),
+            disclaimer: String::new(),
+            api_key: None,
+        }
+    }
+
+    fn dispatcher(name: &str, routes: Vec<Route>, configured: Vec<TelegramChat>) -> Dispatcher {
+        let dir = std::env::temp_dir().join(format!("automatex-notifier-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Dispatcher {
+            routes,
+            subscribers: Subscribers {
+                service_name: "Earthquake".to_string(),
+                store: subscriptions::Store::new(dir.join("subscriptions.json")),
+                client: reqwest::Client::new(),
+                api_key: String::new(),
+                state: Manager::new(dir.join("state.json"), Duration::ZERO),
+                quiet_hours: None,
+                configured,
+                routes: Mutex::new(HashMap::new()),
+            },
+            held_path: dir.join("held_earthquake.json"),
+        }
+    }
+
+    #[tokio::test]
+    async fn released_notifications_stay_saved_until_delivered() {
+        let recorder = Arc::new(Recorder {
+            failing: AtomicBool::new(true),
+            sent: Mutex::new(Vec::new()),
+        });
+        let route = Route::new(recorder.clone(), Severity::Info, None);
+        route.held.lock().unwrap().push(notification("held", Severity::Minor, None));
+        let dispatcher = dispatcher("release", vec![route.clone()], Vec::new());
+        let cfg = service_config();
+
+        dispatcher.dispatch(&[], &cfg).await;
+        assert_eq!(route.held.lock().unwrap().len(), 1);
+        let saved: HeldFile = serde_json::from_slice(&std::fs::read(&dispatcher.held_path).unwrap()).unwrap();
+        assert_eq!(saved.routes["Recorder"][0].id, "held");
+
+        recorder.failing.store(false, Ordering::SeqCst);
+        dispatcher.dispatch(&[notification("new", Severity::Minor, None)], &cfg).await;
+        assert_eq!(*recorder.sent.lock().unwrap(), ["held", "new"]);
+        assert!(route.held.lock().unwrap().is_empty());
+        assert!(!dispatcher.held_path.exists());
+    }
+
+    #[tokio::test]
+    async fn notifications_held_for_unsubscribed_chats_are_dropped() {
+        let dispatcher = dispatcher("unsubscribed", Vec::new(), Vec::new());
+        let route = dispatcher.subscribers.route("7", None);
+        route.held.lock().unwrap().push(notification("held", Severity::Minor, None));
+        dispatcher.subscribers.routes.lock().unwrap().insert(("7".to_string(), None), route);
+
+        dispatcher.save_held().await.unwrap();
+        assert!(dispatcher.subscribers.routes.lock().unwrap().is_empty());
+        assert!(!dispatcher.held_path.exists());
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_their_service_above_their_minimum_score() {
+        let configured = TelegramChat {
+            chat_id: "4".to_string(),
+            thread_id: None,
+            min_severity: Severity::Info,
+        };
+        let dispatcher = dispatcher("targets", Vec::new(), vec![configured]);
+        let subscribe = |chat_id: &str, service: &str, min_score| subscriptions::Subscription {
+            chat_id: chat_id.to_string(),
+            thread_id: None,
+            service: service.to_string(),
+            min_score,
+            area: None,
+        };
+        let store = &dispatcher.subscribers.store;
+        store.subscribe(subscribe("1", "Earthquake", Some(5.0))).await.unwrap();
+        store.subscribe(subscribe("2", "Earthquake", None)).await.unwrap();
+        store.subscribe(subscribe("3", "CVE", None)).await.unwrap();
+        store.subscribe(subscribe("4", "Earthquake", None)).await.unwrap();
+
+        let ns = [
+            notification("small", Severity::Minor, Some(4.0)),
+            notification("large", Severity::Major, Some(6.0)),
+            notification("unscored", Severity::Minor, None),
+        ];
+        let targets: Vec<(String, Vec<String>)> = dispatcher
+            .subscribers
+            .targets(&ns)
+            .await
+            .into_iter()
+            .map(|(route, ns)| {
+                let ids = ns.iter().map(|n| n.get_unique_id().to_string()).collect();
+                (route.notifier.name().to_string(), ids)
+            })
+            .collect();
+        // Chat 3 subscribed to another service and chat 4 already receives it through the configuration.
+        assert_eq!(
+            targets,
+            [
+                ("Telegram (1)".to_string(), vec!["large".to_string()]),
+                ("Telegram (2)".to_string(), vec!["small".to_string(), "large".to_string(), "unscored".to_string()]),
+            ]
+        );
+    }
+}
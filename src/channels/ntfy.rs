@@ -0,0 +1,108 @@
+use super::{plain_text, render, Capabilities, Notifier};
+use crate::{
+    config::{NtfyConfig, ServiceConfig},
+    services::{Notification, Severity},
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use tracing::error;
+
+#[derive(Serialize)]
+struct PublishPayload<'a> {
+    topic: &'a str,
+    title: &'a str,
+    message: String,
+    priority: u8,
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    click: Option<&'a str>,
+    actions: Vec<Action<'a>>,
+}
+
+#[derive(Serialize)]
+struct Action<'a> {
+    action: &'a str,
+    label: &'a str,
+    url: &'a str,
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    config: NtfyConfig,
+}
+
+impl Sender {
+    pub fn new(client: Client, config: NtfyConfig) -> Self {
+        Self { client, config }
+    }
+
+    fn priority(severity: Severity) -> u8 {
+        match severity {
+            Severity::Info => 2,
+            Severity::Minor => 3,
+            Severity::Major => 4,
+            Severity::Critical => 5,
+        }
+    }
+
+    async fn publish(&self, n: &dyn Notification, cfg: &ServiceConfig, silent: bool) -> anyhow::Result<()> {
+        let message = n.message();
+        let mut body = render::fields(&message);
+        if !cfg.disclaimer.is_empty() {
+            body.push('\n');
+            body.push_str(&plain_text(&cfg.disclaimer));
+        }
+
+        let links = message.display_links();
+
+        // JSON publishing goes to the server root so titles may contain non-ASCII characters.
+        let payload = PublishPayload {
+            topic: &self.config.topic,
+            title: &message.heading(),
+            message: body,
+            // Minimum priority shows up without a sound or vibration.
+            priority: if silent { 1 } else { Self::priority(message.severity) },
+            tags: &message.tags,
+            click: message.primary_url(),
+            // ntfy shows at most three action buttons.
+            actions: links
+                .iter()
+                .take(3)
+                .map(|l| Action { action: "view", label: &l.label, url: &l.url })
+                .collect(),
+        };
+
+        let mut req = self.client.post(&self.config.server_url).json(&payload);
+        if !self.config.token.expose().is_empty() {
+            req = req.bearer_auth(self.config.token.expose());
+        }
+        let res = req.send().await?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_else(|_| "Could not read body".to_string());
+            error!(status = %status, body = %body, "ntfy server returned non-200 status");
+            return Err(anyhow::anyhow!("ntfy error: {}", status));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "ntfy" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.publish(n, cfg, false).await
+    }
+
+    async fn send_silent(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.publish(n, cfg, true).await
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limit {
+    pub burst: f64,
+    pub per_second: f64,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: Limit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst);
+        self.updated = now;
+    }
+
+    fn wait_time(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.limit.per_second)
+        }
+    }
+}
+
+// A global token bucket combined with one bucket per key (e.g. per chat).
+// A message is only let through once both buckets have a token available.
+#[derive(Debug)]
+pub struct RateLimiter {
+    global: Mutex<TokenBucket>,
+    keyed: Mutex<HashMap<String, TokenBucket>>,
+    key_limit: fn(&str) -> Limit,
+}
+
+impl RateLimiter {
+    pub fn new(global: Limit, key_limit: fn(&str) -> Limit) -> Self {
+        Self {
+            global: Mutex::new(TokenBucket::new(global)),
+            keyed: Mutex::new(HashMap::new()),
+            key_limit,
+        }
+    }
+
+    pub async fn acquire(&self, key: &str) {
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut global = self.global.lock().unwrap();
+                let mut keyed = self.keyed.lock().unwrap();
+                let bucket = keyed
+                    .entry(key.to_string())
+                    .or_insert_with(|| TokenBucket::new((self.key_limit)(key)));
+
+                global.refill(now);
+                bucket.refill(now);
+                let wait = global.wait_time().max(bucket.wait_time());
+                if wait.is_zero() {
+                    global.tokens -= 1.0;
+                    bucket.tokens -= 1.0;
+                }
+                wait
+            };
+            if wait.is_zero() {
+                return;
+            }
+            time::sleep(wait).await;
+        }
+    }
+
+    // Drains a key's bucket after the remote side asked us to back off.
+    pub fn penalize(&self, key: &str, retry_after: Duration) {
+        let mut keyed = self.keyed.lock().unwrap();
+        let bucket = keyed
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new((self.key_limit)(key)));
+        bucket.tokens = -(retry_after.as_secs_f64() * bucket.limit.per_second);
+        bucket.updated = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn per_key(_: &str) -> Limit {
+        Limit { burst: 2.0, per_second: 1.0 }
+    }
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(Limit { burst: 30.0, per_second: 30.0 }, per_key)
+    }
+
+    fn wait_time(limiter: &RateLimiter, key: &str) -> Duration {
+        let mut keyed = limiter.keyed.lock().unwrap();
+        let bucket = keyed.get_mut(key).unwrap();
+        bucket.refill(Instant::now());
+        bucket.wait_time()
+    }
+
+    #[test]
+    fn bucket_refills_up_to_its_burst() {
+        let mut bucket = TokenBucket::new(Limit { burst: 3.0, per_second: 2.0 });
+        let start = bucket.updated;
+        bucket.tokens = 0.0;
+        assert_eq!(bucket.wait_time(), Duration::from_millis(500));
+
+        bucket.refill(start + Duration::from_millis(500));
+        assert_eq!(bucket.tokens, 1.0);
+        assert_eq!(bucket.wait_time(), Duration::ZERO);
+
+        bucket.refill(start + Duration::from_secs(10));
+        assert_eq!(bucket.tokens, 3.0);
+    }
+
+    #[tokio::test]
+    async fn each_key_has_its_own_burst() {
+        let limiter = limiter();
+        limiter.acquire("a").await;
+        limiter.acquire("a").await;
+        assert!(wait_time(&limiter, "a") > Duration::from_millis(900));
+
+        // Another chat is not held back by the first one.
+        limiter.acquire("b").await;
+        assert_eq!(wait_time(&limiter, "b"), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn penalty_holds_the_key_back_for_retry_after() {
+        let limiter = limiter();
+        limiter.acquire("a").await;
+        limiter.penalize("a", Duration::from_secs(5));
+        let wait = wait_time(&limiter, "a");
+        assert!(wait > Duration::from_secs(5) && wait <= Duration::from_secs(6), "{:?}", wait);
+
+        // A chat that has not sent anything yet is penalized too.
+        limiter.penalize("b", Duration::from_secs(5));
+        assert!(wait_time(&limiter, "b") > Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,61 @@
+use super::{escape_html, plain_text};
+use crate::{config::ServiceConfig, services::Message};
+
+pub fn fields(message: &Message) -> String {
+    message
+        .fields
+        .iter()
+        .map(|f| format!("{}: {}\n", f.name, f.value))
+        .collect()
+}
+
+pub fn text(message: &Message) -> String {
+    let mut text = format!("{}\n\n{}", message.heading(), fields(message));
+    for link in message.display_links() {
+        text.push_str(&format!("{}: {}\n", link.label, link.url));
+    }
+    text
+}
+
+pub fn html(message: &Message) -> String {
+    let mut html = format!("<h2>{}</h2>\n<table>\n", escape_html(&message.heading()));
+    for f in &message.fields {
+        html.push_str(&format!(
+            "<tr><th align=\"left\" valign=\"top\">{}</th><td>{}</td></tr>\n",
+            escape_html(&f.name),
+            escape_html(&f.value.to_string())
+        ));
+    }
+    html.push_str("</table>\n");
+    let links = message.display_links();
+    if !links.is_empty() {
+        let links: Vec<String> = links
+            .iter()
+            .map(|l| format!("<a href=\"{}\">{}</a>", escape_html(&l.url), escape_html(&l.label)))
+            .collect();
+        html.push_str(&format!("<p>{}</p>\n", links.join(" | ")));
+    }
+    html
+}
+
+pub fn footer(cfg: &ServiceConfig) -> (String, String) {
+    let mut text = String::new();
+    let mut html = String::new();
+    if !cfg.buymeacoffee_url.is_empty() || !cfg.disclaimer.is_empty() {
+        text.push_str("\n--------------------\n");
+        html.push_str("<hr>\n");
+    }
+    if !cfg.buymeacoffee_url.is_empty() {
+        text.push_str(&format!("Like this service? Buy Me a Coffee: {}\n", cfg.buymeacoffee_url));
+        html.push_str(&format!(
+            "<p>Like this service? <a href=\"{}\">Buy Me a Coffee ☕</a></p>\n",
+            escape_html(&cfg.buymeacoffee_url)
+        ));
+    }
+    if !cfg.disclaimer.is_empty() {
+        let disclaimer = plain_text(&cfg.disclaimer);
+        text.push_str(&format!("{}\n", disclaimer));
+        html.push_str(&format!("<p><small>{}</small></p>\n", escape_html(&disclaimer)));
+    }
+    (text, html)
+}
@@ -0,0 +1,125 @@
+use super::{plain_text, truncate, Capabilities, Notifier};
+use crate::{
+    config::{ServiceConfig, SlackConfig},
+    services::{Notification, Severity},
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::error;
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    config: SlackConfig,
+}
+
+impl Sender {
+    pub fn new(client: Client, config: SlackConfig) -> Self {
+        Self { client, config }
+    }
+
+    fn severity_label(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Info => ":large_blue_circle: Info",
+            Severity::Minor => ":large_yellow_circle: Minor",
+            Severity::Major => ":large_orange_circle: Major",
+            Severity::Critical => ":red_circle: Critical",
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "Slack" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            // Incoming webhooks are limited to roughly one message per second.
+            send_interval: Duration::from_secs(1),
+            ..Default::default()
+        }
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        let message = n.message();
+
+        let (long, short): (Vec<_>, Vec<_>) = message
+            .fields
+            .iter()
+            .partition(|f| f.is_long());
+
+        let mut blocks: Vec<Value> = vec![json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": truncate(&message.heading(), 150), "emoji": true },
+        })];
+        // Section blocks accept at most 10 fields each.
+        for chunk in short.chunks(10) {
+            let fields: Vec<Value> = chunk
+                .iter()
+                .map(|f| json!({
+                    "type": "mrkdwn",
+                    "text": format!("*{}:*\n{}", escape_mrkdwn(&f.name), escape_mrkdwn(&f.value.to_string())),
+                }))
+                .collect();
+            blocks.push(json!({ "type": "section", "fields": fields }));
+        }
+        for f in long {
+            blocks.push(json!({
+                "type": "section",
+                "text": {
+                    "type": "mrkdwn",
+                    "text": format!("*{}:*\n```{}```", escape_mrkdwn(&f.name), truncate(&escape_mrkdwn(&f.value.to_string()), 2900)),
+                },
+            }));
+        }
+
+        let mut context = vec![Self::severity_label(message.severity).to_string()];
+        for link in message.display_links() {
+            context.push(format!("<{}|{}>", link.url, escape_mrkdwn(&link.label)));
+        }
+        if !cfg.buymeacoffee_url.is_empty() {
+            context.push(format!("<{}|Buy Me a Coffee ☕>", cfg.buymeacoffee_url));
+        }
+        blocks.push(json!({
+            "type": "context",
+            "elements": [{ "type": "mrkdwn", "text": context.join("  |  ") }],
+        }));
+        if !cfg.disclaimer.is_empty() {
+            blocks.push(json!({
+                "type": "context",
+                "elements": [{ "type": "mrkdwn", "text": escape_mrkdwn(&plain_text(&cfg.disclaimer)) }],
+            }));
+        }
+
+        let payload = json!({
+            "text": message.heading(),
+            "blocks": blocks,
+        });
+
+        let res = self.client.post(self.config.webhook_url.expose()).json(&payload).send().await.map_err(reqwest::Error::without_url)?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_else(|_| "Could not read body".to_string());
+            error!(status = %status, body = %body, "Slack webhook returned non-200 status");
+            return Err(anyhow::anyhow!("Slack webhook error: {}", status));
+        }
+        Ok(())
+    }
+}
+
+// Slack mrkdwn only reserves the control characters used for links and mentions.
+fn escape_mrkdwn(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
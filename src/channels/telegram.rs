@@ -0,0 +1,806 @@
+use super::ratelimit::{Limit, RateLimiter};
+use super::{Capabilities, Notifier};
+use crate::{
+    config::{LongMessageMode, ServiceConfig, TelegramChat},
+    services::{GeoPoint, Message, Notification},
+    state::{Manager, SentMessage},
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info, warn};
+
+const MAX_ATTEMPTS: u32 = 5;
+pub const MESSAGE_LIMIT: usize = 4096;
+const FENCE: &str = "```";
+// Chunks are never made smaller than this, so cutting always makes progress.
+const MIN_CHUNK: usize = 32;
+
+// Telegram allows roughly 30 messages per second per bot, one message per second
+// in a private chat and 20 messages per minute in a group or channel.
+fn limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(Limit { burst: 30.0, per_second: 30.0 }, chat_limit))
+}
+
+fn chat_limit(chat_id: &str) -> Limit {
+    if chat_id.starts_with('-') || chat_id.starts_with('@') {
+        Limit { burst: 3.0, per_second: 20.0 / 60.0 }
+    } else {
+        Limit { burst: 1.0, per_second: 1.0 }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Value,
+    description: Option<String>,
+    parameters: Option<ResponseParameters>,
+}
+
+// A request Telegram rejected, e.g. with "Bad Request: message is not modified".
+#[derive(Debug)]
+pub struct ApiError {
+    status: reqwest::StatusCode,
+    description: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Telegram API error: {} {}", self.status.as_u16(), self.description)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Whether Telegram rejected the request with a description containing `text`.
+fn is_api_error(e: &anyhow::Error, text: &str) -> bool {
+    e.downcast_ref::<ApiError>().is_some_and(|e| e.description.contains(text))
+}
+
+#[derive(Deserialize)]
+struct ResponseParameters {
+    retry_after: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct SendMessagePayload<'a> {
+    chat_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i64>,
+    text: &'a str,
+    parse_mode: &'a str,
+    disable_notification: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_parameters: Option<ReplyParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<&'a InlineKeyboardMarkup>,
+}
+
+#[derive(Serialize)]
+struct EditMessageTextPayload<'a> {
+    chat_id: &'a str,
+    message_id: i64,
+    text: &'a str,
+    parse_mode: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_markup: Option<&'a InlineKeyboardMarkup>,
+}
+
+#[derive(Serialize)]
+pub struct InlineKeyboardMarkup {
+    inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+#[derive(Serialize)]
+struct InlineKeyboardButton {
+    text: String,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct ReplyParameters {
+    message_id: i64,
+    allow_sending_without_reply: bool,
+}
+
+#[derive(Serialize)]
+struct SendVenuePayload<'a> {
+    chat_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_thread_id: Option<i64>,
+    latitude: f64,
+    longitude: f64,
+    title: &'a str,
+    address: &'a str,
+    disable_notification: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_parameters: Option<ReplyParameters>,
+}
+
+// Saved next to subscriptions.json, so muted chats stay muted across restarts.
+const MUTES_FILE: &str = "mutes.json";
+
+fn mutes() -> &'static Mutex<HashMap<String, DateTime<Utc>>> {
+    static MUTES: OnceLock<Mutex<HashMap<String, DateTime<Utc>>>> = OnceLock::new();
+    MUTES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub async fn load_mutes() -> anyhow::Result<()> {
+    let data = match tokio::fs::read(MUTES_FILE).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let loaded: HashMap<String, DateTime<Utc>> = serde_json::from_slice(&data)?;
+    let now = Utc::now();
+    let mut mutes = mutes().lock().unwrap();
+    mutes.extend(loaded.into_iter().filter(|(_, until)| *until > now));
+    info!("[{}] Loaded {} muted chat(s).", MUTES_FILE, mutes.len());
+    Ok(())
+}
+
+// Stops notifications to a chat until the given time; `None` unmutes it. The mute applies
+// right away even when it cannot be saved.
+pub async fn mute(chat_id: &str, until: Option<DateTime<Utc>>) -> anyhow::Result<()> {
+    let data = {
+        let mut mutes = mutes().lock().unwrap();
+        match until {
+            Some(until) => mutes.insert(chat_id.to_string(), until),
+            None => mutes.remove(chat_id),
+        };
+        let now = Utc::now();
+        mutes.retain(|_, until| *until > now);
+        serde_json::to_vec_pretty(&*mutes)?
+    };
+    tokio::fs::write(MUTES_FILE, data).await?;
+    Ok(())
+}
+
+pub fn muted_until(chat_id: &str) -> Option<DateTime<Utc>> {
+    let mut mutes = mutes().lock().unwrap();
+    match mutes.get(chat_id) {
+        Some(until) if *until > Utc::now() => Some(*until),
+        Some(_) => {
+            mutes.remove(chat_id);
+            None
+        }
+        None => None,
+    }
+}
+
+// Calls a Bot API method, waiting for the rate limiter when it targets `chat_id`, honouring
+// `retry_after` on 429 and retrying network errors and 5xx responses with exponential backoff.
+pub async fn call<T: Serialize + Sync>(
+    client: &Client,
+    api_key: &str,
+    chat_id: Option<&str>,
+    method: &str,
+    payload: &T,
+) -> anyhow::Result<Value> {
+    let url = format!("https://api.telegram.org/bot{}/{}", api_key, method);
+    let mut backoff = Duration::from_secs(1);
+    let mut attempt = 1;
+    loop {
+        if let Some(chat_id) = chat_id {
+            limiter().acquire(chat_id).await;
+        }
+
+        let (err, wait) = match client.post(&url).json(payload).send().await {
+            Ok(res) => {
+                let status = res.status();
+                let body = res.text().await.unwrap_or_else(|_| "Could not read body".to_string());
+                let (retry_after, description) = match serde_json::from_str::<ApiResponse>(&body) {
+                    Ok(response) if status.is_success() && response.ok => return Ok(response.result),
+                    Ok(response) => (response.parameters.and_then(|p| p.retry_after), response.description),
+                    Err(_) => (None, None),
+                };
+
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    let retry_after = retry_after.map_or(backoff, Duration::from_secs);
+                    let err = anyhow::anyhow!("Telegram API rate limit, retry after {:?}", retry_after);
+                    match chat_id {
+                        // The limiter makes every sender to this chat wait, including the retry below.
+                        Some(chat_id) => {
+                            limiter().penalize(chat_id, retry_after);
+                            (err, Duration::ZERO)
+                        }
+                        None => (err, retry_after),
+                    }
+                } else {
+                    error!(status = %status, body = %body, "Telegram API returned non-200 status");
+                    let err = anyhow::Error::new(ApiError {
+                        status,
+                        description: description.unwrap_or_default(),
+                    });
+                    if !status.is_server_error() {
+                        return Err(err);
+                    }
+                    (err, backoff)
+                }
+            }
+            // The URL contains the bot token, so it is left out of errors that end up in logs.
+            Err(e) => (e.without_url().into(), backoff),
+        };
+
+        if attempt >= MAX_ATTEMPTS {
+            return Err(err);
+        }
+        warn!(chat_id = chat_id.unwrap_or_default(), attempt, "Telegram request failed, retrying: {:?}", err);
+        time::sleep(wait).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    api_key: String,
+    chat: TelegramChat,
+    name: String,
+    state: Manager,
+}
+
+impl Sender {
+    pub fn new(client: Client, api_key: String, chat: TelegramChat, state: Manager) -> Self {
+        let name = match chat.thread_id {
+            Some(thread_id) => format!("Telegram ({}/{})", chat.chat_id, thread_id),
+            None => format!("Telegram ({})", chat.chat_id),
+        };
+        Self {
+            client,
+            api_key,
+            chat,
+            name,
+            state,
+        }
+    }
+
+    // Returns the `message_id` of the sent message.
+    pub async fn send_message(
+        &self,
+        message: &str,
+        keyboard: Option<&InlineKeyboardMarkup>,
+        reply_to: Option<i64>,
+        silent: bool,
+    ) -> anyhow::Result<i64> {
+        let payload = SendMessagePayload {
+            chat_id: &self.chat.chat_id,
+            message_thread_id: self.chat.thread_id,
+            text: message,
+            parse_mode: "MarkdownV2",
+            disable_notification: silent,
+            reply_parameters: reply_to.map(|message_id| ReplyParameters {
+                message_id,
+                allow_sending_without_reply: true,
+            }),
+            reply_markup: keyboard,
+        };
+
+        let result = self.call("sendMessage", &payload).await?;
+        result["message_id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Telegram API response has no message_id"))
+    }
+
+    pub async fn edit_message(
+        &self,
+        message_id: i64,
+        message: &str,
+        keyboard: Option<&InlineKeyboardMarkup>,
+    ) -> anyhow::Result<()> {
+        let payload = EditMessageTextPayload {
+            chat_id: &self.chat.chat_id,
+            message_id,
+            text: message,
+            parse_mode: "MarkdownV2",
+            reply_markup: keyboard,
+        };
+
+        match self.call("editMessageText", &payload).await {
+            Ok(_) => Ok(()),
+            // Only fields the message does not show changed, so it is already up to date.
+            Err(e) if is_api_error(&e, "message is not modified") => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Sends the point as a venue (a tappable map pin), replying to `reply_to` so it stays grouped
+    // with the text report.
+    pub async fn send_venue(
+        &self,
+        title: &str,
+        geo: &GeoPoint,
+        reply_to: Option<i64>,
+        silent: bool,
+    ) -> anyhow::Result<i64> {
+        let payload = SendVenuePayload {
+            chat_id: &self.chat.chat_id,
+            message_thread_id: self.chat.thread_id,
+            latitude: geo.latitude,
+            longitude: geo.longitude,
+            title,
+            address: &geo.label,
+            disable_notification: silent,
+            reply_parameters: reply_to.map(|message_id| ReplyParameters {
+                message_id,
+                allow_sending_without_reply: true,
+            }),
+        };
+
+        let result = self.call("sendVenue", &payload).await?;
+        result["message_id"]
+            .as_i64()
+            .ok_or_else(|| anyhow::anyhow!("Telegram API response has no message_id"))
+    }
+
+    async fn call<T: Serialize + Sync>(&self, method: &str, payload: &T) -> anyhow::Result<Value> {
+        call(&self.client, &self.api_key, Some(&self.chat.chat_id), method, payload).await
+    }
+
+    // Returns the message to send together with the one an update should rewrite. A chat that
+    // never got the original report, e.g. because it subscribed since, gets the update as a new
+    // report instead of one marked as updated.
+    async fn original(&self, n: &dyn Notification) -> (Message, Option<SentMessage>) {
+        let mut message = n.message();
+        if !message.updated {
+            return (message, None);
+        }
+        let sent = self.state.message(n.get_unique_id(), &self.chat.chat_id, self.chat.thread_id).await;
+        message.updated = sent.is_some();
+        (message, sent)
+    }
+
+    async fn deliver(&self, n: &dyn Notification, cfg: &ServiceConfig, silent: bool) -> anyhow::Result<()> {
+        if let Some(until) = muted_until(&self.chat.chat_id) {
+            info!(chat_id = %self.chat.chat_id, notification_id = %n.get_unique_id(), "Chat is muted until {}, skipping notification.", until);
+            return Ok(());
+        }
+
+        let (message, sent) = self.original(n).await;
+        let text = render(&message, cfg);
+        let keyboard = keyboard(&message, cfg);
+
+        let parts = match cfg.telegram_long_messages {
+            LongMessageMode::Split => split_markdown(&text, MESSAGE_LIMIT),
+            LongMessageMode::Truncate => match truncate_markdown(&text, MESSAGE_LIMIT, message.primary_url()) {
+                Some(text) => vec![text],
+                None => split_markdown(&text, MESSAGE_LIMIT),
+            },
+        };
+
+        // Updates rewrite the original report in place, falling back to a reply when it can no
+        // longer be edited or was deleted. Reports spanning several messages are always answered
+        // with a reply, since rewriting only one part would leave the others stale.
+        let mut reply_to = None;
+        if let Some(sent) = sent {
+            let message_id = sent.message_id;
+            if !sent.split && parts.len() == 1 {
+                match self.edit_message(message_id, &parts[0], keyboard.as_ref()).await {
+                    Ok(()) => return Ok(()),
+                    Err(e) if is_api_error(&e, "message can't be edited") || is_api_error(&e, "message to edit not found") => {
+                        warn!(chat_id = %self.chat.chat_id, message_id, "Could not edit message, replying instead: {}", e)
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            reply_to = Some(message_id);
+        }
+
+        // The buttons go on the last part so they follow the complete report.
+        let mut first_message_id = None;
+        let last = parts.len().saturating_sub(1);
+        for (i, part) in parts.iter().enumerate() {
+            let markup = if i == last { keyboard.as_ref() } else { None };
+            let message_id = self.send_message(part, markup, reply_to.take(), silent).await?;
+            first_message_id.get_or_insert(message_id);
+        }
+
+        if let Some(message_id) = first_message_id {
+            self.state
+                .add_message(
+                    n.get_unique_id(),
+                    SentMessage {
+                        chat_id: self.chat.chat_id.clone(),
+                        thread_id: self.chat.thread_id,
+                        message_id,
+                        split: parts.len() > 1,
+                    },
+                )
+                .await;
+        }
+
+        if cfg.telegram_send_location && let Some(geo) = &message.geo {
+            self.send_venue(&message.title, geo, first_message_id, silent).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { &self.name }
+
+    fn capabilities(&self) -> Capabilities {
+        // Pacing is handled by the shared rate limiter instead of a fixed pause.
+        Capabilities::default()
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.deliver(n, cfg, false).await
+    }
+
+    async fn send_silent(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        self.deliver(n, cfg, true).await
+    }
+}
+
+pub fn render(message: &Message, cfg: &ServiceConfig) -> String {
+    let mut text = format!("*{}*\n", escape_markdown(&message.heading()));
+
+    for f in &message.fields {
+        let value = f.value.to_string();
+        if f.is_long() {
+            text.push_str(&format!("\n*{}:*\n```\n{}\n```", escape_markdown(&f.name), escape_code(&value)));
+        } else {
+            text.push_str(&format!("\n*{}:* {}", escape_markdown(&f.name), escape_markdown(&value)));
+        }
+    }
+
+    if !cfg.disclaimer.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&escape_markdown("--------------------"));
+        text.push_str("\n\n");
+        text.push_str(&cfg.disclaimer);
+    }
+    text
+}
+
+// Links are shown as URL buttons below the message rather than inline in the text.
+pub fn keyboard(message: &Message, cfg: &ServiceConfig) -> Option<InlineKeyboardMarkup> {
+    // With native locations enabled the map pin replaces the map link.
+    let links = if cfg.telegram_send_location {
+        message.links.clone()
+    } else {
+        message.display_links()
+    };
+
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = links
+        .chunks(2)
+        .map(|row| {
+            row.iter()
+                .map(|l| InlineKeyboardButton {
+                    text: l.label.clone(),
+                    url: l.url.clone(),
+                })
+                .collect()
+        })
+        .collect();
+    if !cfg.buymeacoffee_url.is_empty() {
+        rows.push(vec![InlineKeyboardButton {
+            text: "Buy Me a Coffee ☕".to_string(),
+            url: cfg.buymeacoffee_url.clone(),
+        }]);
+    }
+
+    (!rows.is_empty()).then_some(InlineKeyboardMarkup { inline_keyboard: rows })
+}
+
+pub fn escape_markdown(s: &str) -> String {
+    let chars = [
+        '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
+    ];
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if chars.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Inside pre and code entities only '`' and '\\' have to be escaped.
+fn escape_code(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+// Inside the (...) part of inline links only ')' and '\\' have to be escaped.
+fn escape_link_url(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(')', "\\)")
+}
+
+// Telegram measures message length in UTF-16 code units.
+fn text_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+// Splits MarkdownV2 text into chunks of at most `limit` units, preferring line boundaries.
+// Lines too long to keep whole are cut at a safe point, and code blocks cut in the middle
+// are closed and reopened in the next chunk.
+fn split_markdown(text: &str, limit: usize) -> Vec<String> {
+    // Room for closing a code fence at the end of a chunk and reopening it in the next one.
+    let max = limit.saturating_sub(2 * (FENCE.len() + 1)).max(MIN_CHUNK);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+
+    for line in text.split('\n') {
+        let mut rest = line;
+        loop {
+            let used = if current.is_empty() { 0 } else { text_len(&current) + 1 };
+            let room = max.saturating_sub(used);
+            if text_len(rest) <= room {
+                if !current.is_empty() {
+                    current.push('\n');
+                }
+                current.push_str(rest);
+                break;
+            }
+
+            if text_len(rest) > max / 2 && room >= 16 {
+                let cut = safe_cut(rest, room, in_code);
+                if !current.is_empty() {
+                    current.push('\n');
+                }
+                current.push_str(rest[..cut].trim_end());
+                rest = rest[cut..].trim_start();
+            }
+            if in_code {
+                current.push('\n');
+                current.push_str(FENCE);
+            }
+            chunks.push(std::mem::take(&mut current));
+            if in_code {
+                current.push_str(FENCE);
+            }
+        }
+        if line.starts_with(FENCE) {
+            in_code = !in_code;
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+// Returns `None` when the suffix, e.g. with a very long link, leaves too little room for the
+// text; the message is then split instead.
+pub fn truncate_markdown(text: &str, limit: usize, read_more_url: Option<&str>) -> Option<String> {
+    if text_len(text) <= limit {
+        return Some(text.to_string());
+    }
+    let suffix = match read_more_url {
+        Some(url) => format!("\n…\n[Read more]({})", escape_link_url(url)),
+        None => "\n…".to_string(),
+    };
+    let room = limit.saturating_sub(text_len(&suffix));
+    if room < MIN_CHUNK + 2 * (FENCE.len() + 1) {
+        return None;
+    }
+    let head = split_markdown(text, room).into_iter().next().unwrap_or_default();
+    Some(head + &suffix)
+}
+
+// Finds the byte offset of the last whitespace within `max` units that does not fall inside an
+// escape sequence, a link or an emphasis entity. Falls back to the last offset that at least
+// keeps escape sequences intact.
+fn safe_cut(s: &str, max: usize, in_code: bool) -> usize {
+    #[derive(PartialEq)]
+    enum Link {
+        None,
+        Text,
+        AfterText,
+        Url,
+    }
+
+    let mut units = 0;
+    let mut escaped = false;
+    let mut link = Link::None;
+    let mut open_entities = 0u8;
+    let mut best_space = None;
+    let mut fallback = 0;
+
+    for (i, c) in s.char_indices() {
+        if units > max {
+            break;
+        }
+        if i > 0 && !escaped {
+            if c.is_whitespace() && link == Link::None && open_entities == 0 {
+                best_space = Some(i);
+            }
+            fallback = i;
+        }
+        units += c.len_utf16();
+
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if c == '\\' {
+            escaped = true;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        link = match (link, c) {
+            (Link::None, '[') => Link::Text,
+            (Link::Text, ']') => Link::AfterText,
+            (Link::AfterText, '(') => Link::Url,
+            (Link::AfterText, _) => Link::None,
+            (Link::Url, ')') => Link::None,
+            (state, _) => state,
+        };
+        match c {
+            '*' => open_entities ^= 1,
+            '_' => open_entities ^= 2,
+            '~' => open_entities ^= 4,
+            '|' => open_entities ^= 8,
+            _ => {}
+        }
+    }
+    best_space.unwrap_or(fallback).max(s.chars().next().map_or(0, char::len_utf8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::Severity;
+
+    #[test]
+    fn groups_and_channels_get_a_larger_burst_but_a_lower_rate() {
+        for chat_id in ["-1001234567890", "@channel"] {
+            let limit = chat_limit(chat_id);
+            assert_eq!(limit.burst, 3.0);
+            assert_eq!(limit.per_second, 20.0 / 60.0);
+        }
+        let limit = chat_limit("123456789");
+        assert_eq!(limit.burst, 1.0);
+        assert_eq!(limit.per_second, 1.0);
+    }
+
+    #[test]
+    fn api_errors_are_matched_by_description() {
+        let e = anyhow::Error::new(ApiError {
+            status: reqwest::StatusCode::BAD_REQUEST,
+            description: "Bad Request: message is not modified: specified new message content and reply markup are exactly the same".to_string(),
+        });
+        assert!(is_api_error(&e, "message is not modified"));
+        assert!(!is_api_error(&e, "message to edit not found"));
+        assert!(!is_api_error(&anyhow::anyhow!("message is not modified"), "message is not modified"));
+        assert_eq!(e.to_string(), "Telegram API error: 400 Bad Request: message is not modified: specified new message content and reply markup are exactly the same");
+    }
+
+    #[test]
+    fn split_closes_and_reopens_code_blocks() {
+        let lines: Vec<String> = (0..20).map(|i| format!("line {} {}", i, "x".repeat(40))).collect();
+        let text = format!("Output:\n{}\n{}\n{}", FENCE, lines.join("\n"), FENCE);
+        let chunks = split_markdown(&text, 300);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(text_len(chunk) <= 300);
+            assert_eq!(chunk.lines().filter(|l| l.starts_with(FENCE)).count() % 2, 0, "unbalanced fences in {:?}", chunk);
+        }
+        assert!(chunks[0].ends_with(FENCE));
+        assert!(chunks[1].starts_with(FENCE));
+    }
+
+    #[test]
+    fn split_cuts_lines_without_whitespace() {
+        let text = "a".repeat(500);
+        let chunks = split_markdown(&text, 100);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| text_len(c) <= 100));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn split_keeps_escape_sequences_together() {
+        let text = "\\.".repeat(200);
+        let chunks = split_markdown(&text, 101);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(text_len(chunk) <= 101);
+            let trailing = chunk.chars().rev().take_while(|&c| c == '\\').count();
+            assert_eq!(trailing % 2, 0, "chunk ends inside an escape: {:?}", chunk);
+        }
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn truncate_appends_read_more_link() {
+        let text = "word ".repeat(100);
+        let truncated = truncate_markdown(&text, 120, Some("https://example.com/a)b")).unwrap();
+
+        assert!(text_len(&truncated) <= 120);
+        assert!(truncated.starts_with("word word"));
+        assert!(truncated.ends_with("\n…\n[Read more](https://example.com/a\\)b)"));
+        assert_eq!(truncate_markdown("short", 120, Some("https://example.com")).as_deref(), Some("short"));
+    }
+
+    #[test]
+    fn truncate_gives_up_when_the_suffix_barely_fits() {
+        let text = "word ".repeat(100);
+        let url = format!("https://example.com/{}", "a".repeat(60));
+
+        assert_eq!(truncate_markdown(&text, 100, Some(&url)), None);
+        assert_eq!(truncate_markdown(&text, 10, Some(&url)), None);
+        let truncated = truncate_markdown(&text, 140, Some(&url)).unwrap();
+        assert!(text_len(&truncated) <= 140);
+        assert!(truncated.ends_with(&format!("[Read more]({})", url)));
+    }
+
+    #[test]
+    fn split_tiny_limits_still_make_progress() {
+        let text = format!("{}\n{}\nsome code\n{}", "a".repeat(100), FENCE, FENCE);
+        for limit in [0, 1, 8, 20] {
+            let chunks = split_markdown(&text, limit);
+            assert!(chunks.len() > 1);
+            assert!(chunks.iter().all(|c| !c.is_empty()));
+        }
+    }
+
+    #[derive(Debug)]
+    struct Revised;
+
+    impl Notification for Revised {
+        fn get_unique_id(&self) -> &str { "quake" }
+        fn get_timestamp(&self) -> i64 { 0 }
+        fn message(&self) -> Message {
+            Message {
+                title: "Earthquake".to_string(),
+                severity: Severity::Minor,
+                fields: Vec::new(),
+                links: Vec::new(),
+                geo: None,
+                tags: Vec::new(),
+                updated: true,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn updates_are_new_reports_for_chats_without_the_original() {
+        let state = Manager::new("unused.json", std::time::Duration::ZERO);
+        let chat = |chat_id: &str| TelegramChat {
+            chat_id: chat_id.to_string(),
+            thread_id: None,
+            min_severity: Severity::Info,
+        };
+        state.add("quake".to_string(), 0).await;
+        state
+            .add_message(
+                "quake",
+                SentMessage {
+                    chat_id: "1".to_string(),
+                    thread_id: None,
+                    message_id: 42,
+                    split: false,
+                },
+            )
+            .await;
+
+        let (message, sent) = Sender::new(Client::new(), String::new(), chat("1"), state.clone()).original(&Revised).await;
+        assert!(message.updated);
+        assert_eq!(sent.map(|s| s.message_id), Some(42));
+
+        let (message, sent) = Sender::new(Client::new(), String::new(), chat("2"), state).original(&Revised).await;
+        assert!(!message.updated);
+        assert!(sent.is_none());
+    }
+}
@@ -0,0 +1,274 @@
+use super::{render, Capabilities, Notifier};
+use crate::{
+    config::{ServiceConfig, WebhookConfig},
+    services::{FieldValue, GeoPoint, Link, Notification, Severity},
+};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::time;
+use tracing::warn;
+
+const SCHEMA_VERSION: u32 = 1;
+const SIGNATURE_HEADER: &str = "X-Automatex-Signature-256";
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    schema_version: u32,
+    service: &'a str,
+    id: &'a str,
+    timestamp: i64,
+    title: &'a str,
+    severity: Severity,
+    updated: bool,
+    url: Option<&'a str>,
+    links: &'a [Link],
+    geo: Option<&'a GeoPoint>,
+    tags: &'a [String],
+    fields: Map<String, Value>,
+    text: String,
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    client: Client,
+    config: WebhookConfig,
+    service_name: String,
+}
+
+impl Sender {
+    pub fn new(client: Client, config: WebhookConfig, service_name: &str) -> Self {
+        Self {
+            client,
+            config,
+            service_name: service_name.to_string(),
+        }
+    }
+
+    fn sign(&self, body: &[u8]) -> anyhow::Result<String> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.config.secret.expose().as_bytes())?;
+        mac.update(body);
+        Ok(format!("sha256={}", hex::encode(mac.finalize().into_bytes())))
+    }
+
+    // Webhook URLs often carry a token, so logs and errors only name the host.
+    async fn post(&self, url: &str, body: &[u8], signature: Option<&str>) -> anyhow::Result<()> {
+        let host = host(url);
+        let mut backoff = Duration::from_secs(1);
+        let mut attempt = 0;
+        loop {
+            let mut req = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_vec());
+            if let Some(signature) = signature {
+                req = req.header(SIGNATURE_HEADER, signature);
+            }
+
+            let err = match req.send().await {
+                Ok(res) if res.status().is_success() => return Ok(()),
+                Ok(res) => {
+                    let status = res.status();
+                    let retryable = status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    let err = anyhow::anyhow!("Webhook returned status {}", status);
+                    if !retryable {
+                        return Err(err);
+                    }
+                    err
+                }
+                Err(e) => e.without_url().into(),
+            };
+
+            if attempt >= self.config.max_retries {
+                return Err(err);
+            }
+            attempt += 1;
+            warn!(host, attempt, "Webhook delivery failed, retrying in {:?}: {:?}", backoff, err);
+            time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for Sender {
+    fn name(&self) -> &str { "Webhook" }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    async fn send(&self, n: &dyn Notification, cfg: &ServiceConfig) -> anyhow::Result<()> {
+        let message = n.message();
+        let fields = message
+            .fields
+            .iter()
+            .map(|f| {
+                let value = match &f.value {
+                    FieldValue::Text(s) => Value::from(s.as_str()),
+                    FieldValue::Number { value, .. } => Value::from(*value),
+                    FieldValue::Time(ts) => chrono::DateTime::from_timestamp(*ts, 0)
+                        .map(|t| Value::from(t.to_rfc3339()))
+                        .unwrap_or(Value::Null),
+                };
+                (f.key.to_string(), value)
+            })
+            .collect();
+        let (footer_text, _) = render::footer(cfg);
+
+        let payload = WebhookPayload {
+            schema_version: SCHEMA_VERSION,
+            service: &self.service_name,
+            id: n.get_unique_id(),
+            timestamp: n.get_timestamp(),
+            title: &message.title,
+            severity: message.severity,
+            updated: message.updated,
+            url: message.primary_url(),
+            links: &message.links,
+            geo: message.geo.as_ref(),
+            tags: &message.tags,
+            fields,
+            text: render::text(&message) + &footer_text,
+        };
+        let body = serde_json::to_vec(&payload)?;
+        let signature = if self.config.secret.expose().is_empty() {
+            None
+        } else {
+            Some(self.sign(&body)?)
+        };
+
+        let mut failed = Vec::new();
+        for url in &self.config.urls {
+            if let Err(e) = self.post(url.expose(), &body, signature.as_deref()).await {
+                failed.push(format!("{}: {}", host(url.expose()), e));
+            }
+        }
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!("Webhook delivery failed for {}", failed.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::tests::service_config;
+    use crate::services::{Field, Message};
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use tokio::sync::mpsc;
+
+    #[derive(Debug)]
+    struct Quake;
+
+    impl Notification for Quake {
+        fn get_unique_id(&self) -> &str { "us7000abcd" }
+        fn get_timestamp(&self) -> i64 { 1_700_000_000 }
+
+        fn message(&self) -> Message {
+            Message {
+                title: "Earthquake".to_string(),
+                severity: Severity::Major,
+                fields: vec![
+                    Field::number("magnitude", "Magnitude", 6.1, 1),
+                    Field::text("location", "Location", "Hualien"),
+                    Field::time("time", "Time", 1_700_000_000),
+                ],
+                links: vec![Link::new("Details", "https://example.com/quake")],
+                geo: Some(GeoPoint {
+                    latitude: 23.9,
+                    longitude: 121.6,
+                    label: "Hualien".to_string(),
+                }),
+                tags: vec!["earthquake".to_string()],
+                updated: false,
+            }
+        }
+    }
+
+    fn sender(urls: Vec<String>, secret: &str) -> Sender {
+        let config = WebhookConfig {
+            urls: urls.into_iter().map(Into::into).collect(),
+            secret: secret.to_string().into(),
+            max_retries: 0,
+        };
+        Sender::new(Client::new(), config, "Earthquake")
+    }
+
+    #[test]
+    fn signature_is_hmac_sha256_of_the_body() {
+        let signature = sender(Vec::new(), "key").sign(b"The quick brown fox jumps over the lazy dog").unwrap();
+        assert_eq!(signature, "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+    }
+
+    #[tokio::test]
+    async fn posts_a_signed_stable_payload() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                tx.send((headers, body)).unwrap();
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let sender = sender(vec![format!("http://{}/hook?token=t", addr)], "s3cret");
+        sender.send(&Quake, &service_config()).await.unwrap();
+        let (headers, body) = rx.recv().await.unwrap();
+
+        assert_eq!(headers[SIGNATURE_HEADER], sender.sign(&body).unwrap().as_str());
+        assert_eq!(headers["content-type"], "application/json");
+
+        let payload: Value = serde_json::from_slice(&body).unwrap();
+        let mut keys: Vec<&str> = payload.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            ["fields", "geo", "id", "links", "schema_version", "service", "severity", "tags", "text", "timestamp", "title", "updated", "url"]
+        );
+        assert_eq!(payload["schema_version"], 1);
+        assert_eq!(payload["service"], "Earthquake");
+        assert_eq!(payload["id"], "us7000abcd");
+        assert_eq!(payload["timestamp"], 1_700_000_000);
+        assert_eq!(payload["severity"], "major");
+        assert_eq!(payload["url"], "https://example.com/quake");
+        assert_eq!(payload["links"][0]["label"], "Details");
+        assert_eq!(payload["geo"]["latitude"], 23.9);
+        assert_eq!(payload["fields"]["magnitude"], 6.1);
+        assert_eq!(payload["fields"]["location"], "Hualien");
+        assert_eq!(payload["fields"]["time"], "2023-11-14T22:13:20+00:00");
+        assert!(payload["text"].as_str().unwrap().contains("Hualien"));
+    }
+
+    #[tokio::test]
+    async fn unsigned_without_a_secret() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap| async move {
+                tx.send(headers).unwrap();
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        sender(vec![format!("http://{}/hook", addr)], "").send(&Quake, &service_config()).await.unwrap();
+        assert!(!rx.recv().await.unwrap().contains_key(SIGNATURE_HEADER));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    channels::{self, render, telegram, Notifier},
+    config::{self, Config, ServiceConfig, TelegramChat},
+    monitors,
+    services::{format_time, Field, Message, Notification, NotificationService, Severity},
+    state::Manager,
+};
+use anyhow::Context;
+use clap::builder::{OsStringValueParser, TypedValueParser};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+#[derive(Parser)]
+#[command(version, about = "Monitors earthquakes, rocket launches, space weather and vulnerabilities")]
+pub struct Cli {
+    // Parsed as a plain string because `PathBuf` rejects the empty value a copied
+    // `.env.example` may set; `main` treats it as no config file.
+    #[arg(
+        long,
+        global = true,
+        env = "CONFIG_FILE",
+        value_parser = OsStringValueParser::new().map(PathBuf::from),
+        help = "TOML config file; environment variables take precedence over it"
+    )]
+    pub config: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Run the monitors, the bot and the web server (the default)")]
+    Serve,
+    #[command(about = "Check the configuration and report every problem without starting the server")]
+    ValidateConfig,
+    #[command(about = "Check a service and print what it would notify, without sending or saving anything")]
+    Check {
+        #[arg(help = "Service name or alias, e.g. earthquake or quake")]
+        service: String,
+        #[arg(long, help = "Check once and exit instead of checking at the service's interval")]
+        once: bool,
+    },
+    #[command(about = "Send a test notification to a configured channel")]
+    SendTest {
+        #[arg(help = "telegram, discord, slack, email, matrix, webhook, ntfy or gotify")]
+        channel: String,
+    },
+    #[command(about = "Inspect or reset the IDs a service has already notified about")]
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    #[command(about = "Render the notifications for a saved upstream API response")]
+    Render {
+        #[arg(help = "Service name or alias, e.g. earthquake or quake")]
+        service: String,
+        #[arg(long, help = "JSON file with a response of the service's upstream API")]
+        fixture: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StateAction {
+    #[command(about = "List the remembered IDs")]
+    List { service: String },
+    #[command(about = "Forget every ID, so the next check notifies about everything again; stop the server first")]
+    Purge { service: String },
+    #[command(about = "Print the state as JSON")]
+    Export { service: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Text,
+    Html,
+    Telegram,
+}
+
+pub fn validate_config(config: &Config) {
+    let enabled: Vec<&str> = config
+        .services()
+        .into_iter()
+        .filter(|(_, s)| s.enabled)
+        .map(|(name, _)| name)
+        .collect();
+    println!(
+        "Configuration is valid: {} service(s) enabled ({}), {} extra channel(s).",
+        enabled.len(),
+        enabled.join(", "),
+        config.channels.len()
+    );
+}
+
+fn service(config: &Config, name: &str, client: &reqwest::Client) -> anyhow::Result<Box<dyn NotificationService>> {
+    let name = config::service_name(name).with_context(|| {
+        let names: Vec<&str> = config.services().iter().map(|(name, _)| *name).collect();
+        format!("Unknown service '{}', expected one of: {}", name, names.join(", "))
+    })?;
+    let (name, cfg) = config
+        .services()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .expect("service names come from the config");
+    Ok(monitors::create(name, cfg.clone(), client.clone()))
+}
+
+fn print(ns: &[Box<dyn Notification>], cfg: &ServiceConfig, format: Format) {
+    let (footer_text, footer_html) = render::footer(cfg);
+    for n in ns {
+        let message = n.message();
+        match format {
+            Format::Text => println!("{}{}", render::text(&message), footer_text),
+            Format::Html => println!("{}{}", render::html(&message), footer_html),
+            Format::Telegram => println!("{}\n", telegram::render(&message, cfg)),
+        }
+    }
+}
+
+// State is neither loaded nor saved, so a running server is not affected. Repeated checks
+// only print what is new since the previous one.
+pub async fn check(config: &Config, name: &str, once: bool) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let s = service(config, name, &client)?;
+    let mut interval = time::interval(s.get_config().check_interval);
+    loop {
+        interval.tick().await;
+        let mut ns = s.check_for_notifications().await?;
+        ns.sort_by_key(|n| n.get_timestamp());
+        println!("{}: {} new notification(s)\n", s.name(), ns.len());
+        print(&ns, s.get_config(), Format::Text);
+        if once {
+            return Ok(());
+        }
+    }
+}
+
+pub async fn render(config: &Config, name: &str, fixture: &Path, format: Format) -> anyhow::Result<()> {
+    let s = service(config, name, &reqwest::Client::new())?;
+    let data = std::fs::read(fixture).with_context(|| format!("Could not read fixture {}", fixture.display()))?;
+    let response = serde_json::from_slice(&data).with_context(|| format!("Invalid JSON in fixture {}", fixture.display()))?;
+    let mut ns = s.fixture_notifications(response).await?;
+    ns.sort_by_key(|n| n.get_timestamp());
+    print(&ns, s.get_config(), format);
+    Ok(())
+}
+
+pub async fn state(config: &Config, action: &StateAction) -> anyhow::Result<()> {
+    let name = match action {
+        StateAction::List { service } | StateAction::Purge { service } | StateAction::Export { service } => service,
+    };
+    let s = service(config, name, &reqwest::Client::new())?;
+    let state = s.get_state_manager();
+    state.load().await?;
+
+    match action {
+        StateAction::List { .. } => {
+            let entries = state.entries().await;
+            for e in &entries {
+                let revision = e.revision.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+                println!("{}  {}{}, {} message(s)", format_time(e.timestamp), e.id, revision, e.messages.len());
+            }
+            println!("{}: {} remembered ID(s)", s.name(), entries.len());
+        }
+        StateAction::Purge { .. } => {
+            let count = state.entries().await.len();
+            state.purge().await;
+            state.save().await?;
+            println!("{}: forgot {} ID(s)", s.name(), count);
+        }
+        StateAction::Export { .. } => {
+            println!("{}", serde_json::to_string_pretty(&state.entries().await)?);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct TestNotification {
+    id: String,
+    time: i64,
+}
+
+impl Notification for TestNotification {
+    fn get_unique_id(&self) -> &str { &self.id }
+    fn get_timestamp(&self) -> i64 { self.time }
+
+    fn message(&self) -> Message {
+        Message {
+            title: "🧪 Test notification".to_string(),
+            severity: Severity::Info,
+            fields: vec![
+                Field::text("message", "Message", "Automatex Notifier can deliver to this channel."),
+                Field::time("sent", "Sent", self.time),
+            ],
+            links: Vec::new(),
+            geo: None,
+            tags: vec!["test".to_string()],
+            updated: false,
+        }
+    }
+}
+
+// Sends directly through the channel, so severity filters and quiet hours do not apply.
+pub async fn send_test(config: &Config, channel: &str) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let services = config.services();
+    let (service_name, cfg) = services
+        .iter()
+        .find(|(_, s)| s.enabled)
+        .unwrap_or(&services[0]);
+    let now = chrono::Utc::now().timestamp();
+    let n = TestNotification {
+        id: format!("test-{}", now),
+        time: now,
+    };
+
+    let notifiers: Vec<Arc<dyn Notifier>> = if channel.eq_ignore_ascii_case("telegram") {
+        if cfg.telegram_api_key.expose().is_empty() {
+            return Err(anyhow::anyhow!("TELEGRAM_API_KEY is required to send to Telegram"));
+        }
+        let mut chats: Vec<&TelegramChat> = Vec::new();
+        for (_, s) in services.iter().filter(|(_, s)| s.enabled) {
+            for chat in &s.telegram_chats {
+                if !chats.iter().any(|c| c.chat_id == chat.chat_id && c.thread_id == chat.thread_id) {
+                    chats.push(chat);
+                }
+            }
+        }
+        // Sent message IDs only matter for updates, so they are kept in a state that is never saved.
+        let state = Manager::new("test_messages.json", Duration::ZERO);
+        chats
+            .into_iter()
+            .map(|chat| -> Arc<dyn Notifier> {
+                Arc::new(telegram::Sender::new(client.clone(), cfg.telegram_api_key.expose().to_string(), chat.clone(), state.clone()))
+            })
+            .collect()
+    } else {
+        config
+            .channels
+            .iter()
+            .filter(|c| c.kind.name().eq_ignore_ascii_case(channel))
+            .map(|c| channels::notifier(&c.kind, &client, service_name))
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    if notifiers.is_empty() {
+        let has_chats = services.iter().any(|(_, s)| s.enabled && !s.telegram_chats.is_empty());
+        let mut configured = if has_chats { vec!["telegram"] } else { Vec::new() };
+        configured.extend(config.channels.iter().map(|c| c.kind.name()));
+        return Err(anyhow::anyhow!("No '{}' channel is configured; configured channels: {}", channel, configured.join(", ")));
+    }
+
+    let mut failed = 0;
+    for notifier in &notifiers {
+        match notifier.send(&n, cfg).await {
+            Ok(()) => println!("Sent a test notification to {}", notifier.name()),
+            Err(e) => {
+                eprintln!("Could not send a test notification to {}: {:#}", notifier.name(), e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} test notification(s) failed", failed, notifiers.len()));
+    }
+    Ok(())
+}
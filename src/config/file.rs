@@ -0,0 +1,302 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+// Layout of the optional TOML config file. Every setting corresponds to one of the
+// environment variables, which keep working and take precedence over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct File {
+    buymeacoffee_url: Option<String>,
+    admin_token: Option<String>,
+    bind_address: Option<String>,
+    telegram: Telegram,
+    quiet_hours: QuietHours,
+    services: Services,
+    channels: Channels,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Telegram {
+    api_key: Option<String>,
+    chats: Option<Vec<String>>,
+    min_severity: Option<String>,
+    long_messages: Option<String>,
+    send_location: Option<bool>,
+    quiet_hours: Option<String>,
+    updates: Option<String>,
+    webhook_secret: Option<String>,
+    webhook_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct QuietHours {
+    window: Option<String>,
+    timezone: Option<String>,
+    mode: Option<String>,
+    bypass_severity: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Services {
+    earthquake: Service,
+    rocket_launch: Service,
+    space_weather: Service,
+    vulnerability: Service,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Service {
+    enabled: Option<bool>,
+    interval_secs: Option<u64>,
+    telegram_chats: Option<Vec<String>>,
+    quiet_hours: Option<String>,
+    disclaimer: Option<String>,
+    // Only used by space weather, for the NASA API.
+    api_key: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Channels {
+    discord: Option<Discord>,
+    slack: Option<Slack>,
+    email: Option<Email>,
+    matrix: Option<Matrix>,
+    webhook: Option<Webhook>,
+    ntfy: Option<Ntfy>,
+    gotify: Option<Gotify>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Discord {
+    webhook_url: Option<String>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Slack {
+    webhook_url: Option<String>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Email {
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_username: Option<String>,
+    smtp_password: Option<String>,
+    smtp_tls: Option<String>,
+    from: Option<String>,
+    to: Option<Vec<String>>,
+    batch: Option<bool>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Matrix {
+    homeserver_url: Option<String>,
+    access_token: Option<String>,
+    room_id: Option<String>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Webhook {
+    urls: Option<Vec<String>>,
+    secret: Option<String>,
+    max_retries: Option<u32>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Ntfy {
+    server_url: Option<String>,
+    topic: Option<String>,
+    token: Option<String>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Gotify {
+    server_url: Option<String>,
+    token: Option<String>,
+    services: Option<Vec<String>>,
+    min_severity: Option<String>,
+    quiet_hours: Option<String>,
+}
+
+// A file value in the same textual form as the environment variable it stands in for.
+trait Value {
+    fn into_var(self) -> String;
+}
+
+impl Value for String {
+    fn into_var(self) -> String {
+        self
+    }
+}
+
+impl Value for bool {
+    fn into_var(self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for u64 {
+    fn into_var(self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for u32 {
+    fn into_var(self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for u16 {
+    fn into_var(self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for Vec<String> {
+    fn into_var(self) -> String {
+        self.join(",")
+    }
+}
+
+#[derive(Default)]
+struct Vars(HashMap<String, String>);
+
+impl Vars {
+    fn set(&mut self, key: &str, value: Option<impl Value>) {
+        if let Some(value) = value {
+            self.0.insert(key.to_string(), value.into_var());
+        }
+    }
+
+    fn routing(&mut self, prefix: &str, services: Option<Vec<String>>, min_severity: Option<String>, quiet_hours: Option<String>) {
+        self.set(&format!("{}_SERVICES", prefix), services);
+        self.set(&format!("{}_MIN_SEVERITY", prefix), min_severity);
+        self.set(&format!("{}_QUIET_HOURS", prefix), quiet_hours);
+    }
+
+    fn service(&mut self, prefix: &str, api_key_var: Option<&str>, service: Service) {
+        self.set(&format!("{}_ENABLED", prefix), service.enabled);
+        self.set(&format!("{}_INTERVAL_SECS", prefix), service.interval_secs);
+        self.set(&format!("{}_TELEGRAM_CHATS", prefix), service.telegram_chats);
+        self.set(&format!("{}_QUIET_HOURS", prefix), service.quiet_hours);
+        self.set(&format!("{}_DISCLAIMER", prefix), service.disclaimer);
+        if let Some(api_key_var) = api_key_var {
+            self.set(api_key_var, service.api_key);
+        }
+    }
+}
+
+impl File {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Could not read config file {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    // Flattens the file into the environment variables it corresponds to.
+    pub fn into_vars(self) -> HashMap<String, String> {
+        let mut vars = Vars::default();
+        vars.set("BUYMEACOFFEE_URL", self.buymeacoffee_url);
+        vars.set("ADMIN_TOKEN", self.admin_token);
+        vars.set("BIND_ADDRESS", self.bind_address);
+
+        let telegram = self.telegram;
+        vars.set("TELEGRAM_API_KEY", telegram.api_key);
+        vars.set("TELEGRAM_CHAT_ID", telegram.chats);
+        vars.set("TELEGRAM_MIN_SEVERITY", telegram.min_severity);
+        vars.set("TELEGRAM_LONG_MESSAGES", telegram.long_messages);
+        vars.set("TELEGRAM_SEND_LOCATION", telegram.send_location);
+        vars.set("TELEGRAM_QUIET_HOURS", telegram.quiet_hours);
+        vars.set("TELEGRAM_UPDATES", telegram.updates);
+        vars.set("TELEGRAM_WEBHOOK_SECRET", telegram.webhook_secret);
+        vars.set("TELEGRAM_WEBHOOK_URL", telegram.webhook_url);
+
+        vars.set("QUIET_HOURS", self.quiet_hours.window);
+        vars.set("QUIET_HOURS_TIMEZONE", self.quiet_hours.timezone);
+        vars.set("QUIET_HOURS_MODE", self.quiet_hours.mode);
+        vars.set("QUIET_HOURS_BYPASS_SEVERITY", self.quiet_hours.bypass_severity);
+
+        let services = self.services;
+        vars.service("EARTHQUAKE", None, services.earthquake);
+        vars.service("ROCKETLAUNCH", None, services.rocket_launch);
+        vars.service("SPACEWEATHER", Some("NASA_API_KEY"), services.space_weather);
+        vars.service("VULNERABILITY", None, services.vulnerability);
+
+        let channels = self.channels;
+        if let Some(discord) = channels.discord {
+            vars.set("DISCORD_WEBHOOK_URL", discord.webhook_url);
+            vars.routing("DISCORD", discord.services, discord.min_severity, discord.quiet_hours);
+        }
+        if let Some(slack) = channels.slack {
+            vars.set("SLACK_WEBHOOK_URL", slack.webhook_url);
+            vars.routing("SLACK", slack.services, slack.min_severity, slack.quiet_hours);
+        }
+        if let Some(email) = channels.email {
+            vars.set("SMTP_HOST", email.smtp_host);
+            vars.set("SMTP_PORT", email.smtp_port);
+            vars.set("SMTP_USERNAME", email.smtp_username);
+            vars.set("SMTP_PASSWORD", email.smtp_password);
+            vars.set("SMTP_TLS", email.smtp_tls);
+            vars.set("EMAIL_FROM", email.from);
+            vars.set("EMAIL_TO", email.to);
+            vars.set("EMAIL_BATCH", email.batch);
+            vars.routing("EMAIL", email.services, email.min_severity, email.quiet_hours);
+        }
+        if let Some(matrix) = channels.matrix {
+            vars.set("MATRIX_HOMESERVER_URL", matrix.homeserver_url);
+            vars.set("MATRIX_ACCESS_TOKEN", matrix.access_token);
+            vars.set("MATRIX_ROOM_ID", matrix.room_id);
+            vars.routing("MATRIX", matrix.services, matrix.min_severity, matrix.quiet_hours);
+        }
+        if let Some(webhook) = channels.webhook {
+            vars.set("WEBHOOK_URLS", webhook.urls);
+            vars.set("WEBHOOK_SECRET", webhook.secret);
+            vars.set("WEBHOOK_MAX_RETRIES", webhook.max_retries);
+            vars.routing("WEBHOOK", webhook.services, webhook.min_severity, webhook.quiet_hours);
+        }
+        if let Some(ntfy) = channels.ntfy {
+            vars.set("NTFY_SERVER_URL", ntfy.server_url);
+            vars.set("NTFY_TOPIC", ntfy.topic);
+            vars.set("NTFY_TOKEN", ntfy.token);
+            vars.routing("NTFY", ntfy.services, ntfy.min_severity, ntfy.quiet_hours);
+        }
+        if let Some(gotify) = channels.gotify {
+            vars.set("GOTIFY_SERVER_URL", gotify.server_url);
+            vars.set("GOTIFY_TOKEN", gotify.token);
+            vars.routing("GOTIFY", gotify.services, gotify.min_severity, gotify.quiet_hours);
+        }
+        vars.0
+    }
+}
@@ -0,0 +1,956 @@
+use crate::services::Severity;
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use lettre::message::Mailbox;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+
+mod file;
+
+// Settings are looked up in the environment (including `.env`) first and then in the
+// config file, so secrets can stay out of the file and existing setups keep working.
+// Invalid values are collected as problems instead of failing on the first one.
+struct Settings {
+    env: HashMap<String, String>,
+    file: HashMap<String, String>,
+    problems: RefCell<Vec<String>>,
+}
+
+impl Settings {
+    fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let file = match path {
+            Some(path) => {
+                let vars = file::File::read(path)?.into_vars();
+                info!("Loaded config file {}", path.display());
+                vars
+            }
+            None => HashMap::new(),
+        };
+        let settings = Self {
+            env: env::vars().collect(),
+            file,
+            problems: RefCell::new(Vec::new()),
+        };
+        // Editing these in the file and reloading would otherwise change nothing without a word.
+        for key in settings.hidden_file_keys() {
+            warn!("'{}' in the config file is ignored because the environment sets it", key);
+        }
+        Ok(settings)
+    }
+
+    fn set_in_env(&self, key: &str) -> bool {
+        [key.to_string(), format!("{}_FILE", key)]
+            .iter()
+            .any(|k| self.env.get(k).is_some_and(|v| !v.is_empty()))
+    }
+
+    fn hidden_file_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .file
+            .keys()
+            .filter(|key| self.set_in_env(key))
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    // Shared settings such as QUIET_HOURS are read once per service, so report each problem once.
+    fn problem(&self, problem: String) {
+        let mut problems = self.problems.borrow_mut();
+        if !problems.contains(&problem) {
+            problems.push(problem);
+        }
+    }
+
+    // `<KEY>_FILE` names a file holding the value, e.g. a Docker or Kubernetes secret mount.
+    fn get_optional(&self, key: &str) -> Option<String> {
+        if let Some(value) = self.env.get(key).filter(|v| !v.is_empty()) {
+            return Some(value.clone());
+        }
+        let file_key = format!("{}_FILE", key);
+        if let Some(path) = self.env.get(&file_key).filter(|v| !v.is_empty()) {
+            return match std::fs::read_to_string(path) {
+                Ok(value) => Some(value.trim().to_string()).filter(|v| !v.is_empty()),
+                Err(e) => {
+                    self.problem(format!("{} '{}' could not be read: {}", file_key, path, e));
+                    None
+                }
+            };
+        }
+        self.file.get(key).filter(|v| !v.is_empty()).cloned()
+    }
+
+    fn get(&self, key: &str, default: &str) -> String {
+        self.get_optional(key).unwrap_or_else(|| {
+            warn!("Setting '{}' not found, using default value.", key);
+            default.to_string()
+        })
+    }
+
+    // Like `get_optional`, but reports a problem when the setting is missing.
+    fn get_required(&self, key: &str, why: &str) -> String {
+        self.get_optional(key).unwrap_or_else(|| {
+            self.problem(format!("{} is required {}", key, why));
+            String::new()
+        })
+    }
+
+    fn get_parsed<T>(&self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        match self.get_optional(key).map(|v| (v.parse::<T>(), v)) {
+            Some((Ok(value), _)) => value,
+            Some((Err(e), value)) => {
+                self.problem(format!("{} '{}' is invalid: {}", key, value, e));
+                default
+            }
+            None => default,
+        }
+    }
+
+    fn get_duration(&self, key: &str, default_secs: u64) -> Duration {
+        if self.get_optional(key).is_none() {
+            warn!("Setting '{}' not found, using default value.", key);
+        }
+        let secs = self.get_parsed(key, default_secs);
+        if secs == 0 {
+            self.problem(format!("{} must be greater than zero", key));
+            return Duration::from_secs(default_secs);
+        }
+        Duration::from_secs(secs)
+    }
+
+    fn get_bool(&self, key: &str, default: bool) -> bool {
+        match self.get_optional(key).as_deref() {
+            None => default,
+            Some("true" | "1") => true,
+            Some("false" | "0") => false,
+            Some(other) => {
+                self.problem(format!("{} '{}' is invalid, expected true or false", key, other));
+                default
+            }
+        }
+    }
+
+    // One of `choices`, which lists the accepted values with the default first.
+    fn get_choice<'a>(&self, key: &str, choices: &[&'a str]) -> &'a str {
+        match self.get_optional(key) {
+            None => choices[0],
+            Some(value) => match choices.iter().find(|c| value.eq_ignore_ascii_case(c)) {
+                Some(choice) => choice,
+                None => {
+                    self.problem(format!("{} '{}' is invalid, expected one of: {}", key, value, choices.join(", ")));
+                    choices[0]
+                }
+            },
+        }
+    }
+
+    fn get_severity(&self, key: &str, default: Severity) -> Severity {
+        self.get_parsed(key, default)
+    }
+
+    fn get_url(&self, key: &str) -> Option<String> {
+        let url = self.get_optional(key)?;
+        self.check_url(key, &url);
+        Some(url)
+    }
+
+    fn get_urls(&self, key: &str) -> Vec<String> {
+        let urls = self.get_list(key);
+        for url in &urls {
+            self.check_url(key, url);
+        }
+        urls
+    }
+
+    fn check_url(&self, key: &str, url: &str) {
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => {}
+            Ok(_) => self.problem(format!("{} '{}' is not an http(s) URL", key, url)),
+            Err(e) => self.problem(format!("{} '{}' is not a valid URL: {}", key, url, e)),
+        }
+    }
+
+    // Addresses such as `alerts@example.com` or `Alerts <alerts@example.com>`, comma-separated.
+    fn get_mailboxes(&self, key: &str) -> Vec<Mailbox> {
+        self.get_list(key)
+            .iter()
+            .filter_map(|address| match address.parse::<Mailbox>() {
+                Ok(mailbox) => Some(mailbox),
+                Err(e) => {
+                    self.problem(format!("{} has an invalid address '{}': {}", key, address, e));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn get_list(&self, key: &str) -> Vec<String> {
+        self.get_optional(key)
+            .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    fn get_telegram_chats(&self, key: &str, default: &[TelegramChat], default_severity: Severity) -> Vec<TelegramChat> {
+        let entries = self.get_list(key);
+        if entries.is_empty() {
+            return default.to_vec();
+        }
+        entries
+            .iter()
+            .filter_map(|entry| match TelegramChat::parse(entry, default_severity) {
+                Ok(chat) => Some(chat),
+                Err(e) => {
+                    self.problem(format!("{} has an invalid Telegram chat '{}': {}", key, entry, e));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn get_quiet_hours(&self, key: &str) -> Option<QuietHours> {
+        let value = self.get_optional(key)?;
+        let timezone = self.get_parsed("QUIET_HOURS_TIMEZONE", Tz::UTC);
+        let mode = match self.get_choice("QUIET_HOURS_MODE", &["silent", "hold"]) {
+            "hold" => QuietMode::Hold,
+            _ => QuietMode::Silent,
+        };
+        let bypass_severity = self.get_severity("QUIET_HOURS_BYPASS_SEVERITY", Severity::Critical);
+
+        match QuietHours::parse(&value, timezone, mode, bypass_severity) {
+            Ok(quiet_hours) => Some(quiet_hours),
+            Err(e) => {
+                self.problem(format!("{} '{}' is invalid: {}", key, value, e));
+                None
+            }
+        }
+    }
+}
+
+// A Telegram destination, written as `chat_id[/thread_id][@min_severity]`,
+// e.g. `-1001234567890/42@major` for a forum topic that only receives major alerts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelegramChat {
+    pub chat_id: String,
+    pub thread_id: Option<i64>,
+    pub min_severity: Severity,
+}
+
+impl TelegramChat {
+    fn parse(s: &str, default_severity: Severity) -> anyhow::Result<Self> {
+        // A leading `@` is part of a channel username rather than the severity separator.
+        let (target, min_severity) = match s.rsplit_once('@') {
+            Some((target, severity)) if !target.is_empty() => (target, severity.parse()?),
+            _ => (s, default_severity),
+        };
+        let (chat_id, thread_id) = match target.split_once('/') {
+            Some((chat_id, thread_id)) => (chat_id, Some(thread_id.trim().parse()?)),
+            None => (target, None),
+        };
+        let chat_id = chat_id.trim();
+        if chat_id.is_empty() {
+            return Err(anyhow::anyhow!("chat ID is empty"));
+        }
+        // Either a numeric ID or the @username of a public channel.
+        if chat_id.parse::<i64>().is_err() && !(chat_id.starts_with('@') && chat_id.len() > 1) {
+            return Err(anyhow::anyhow!("chat ID '{}' is neither a number nor an @username", chat_id));
+        }
+        Ok(Self {
+            chat_id: chat_id.to_string(),
+            thread_id,
+            min_severity,
+        })
+    }
+}
+
+// What happens to notifications below the bypass severity during quiet hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuietMode {
+    // Deliver right away without a sound or vibration where the channel supports it.
+    #[default]
+    Silent,
+    // Keep them back and deliver them once the window ends.
+    Hold,
+}
+
+// A daily window written as `HH:MM-HH:MM[ Area/City]`, e.g. `22:00-07:00 Europe/Berlin`.
+// Windows may wrap around midnight; times are in QUIET_HOURS_TIMEZONE unless a zone is given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub timezone: Tz,
+    pub mode: QuietMode,
+    // Notifications at or above this severity ignore quiet hours.
+    pub bypass_severity: Severity,
+}
+
+impl QuietHours {
+    fn parse(s: &str, timezone: Tz, mode: QuietMode, bypass_severity: Severity) -> anyhow::Result<Self> {
+        let (window, timezone) = match s.trim().split_once(' ') {
+            Some((window, tz)) => (window, tz.trim().parse::<Tz>().map_err(|e| anyhow::anyhow!("{}", e))?),
+            None => (s.trim(), timezone),
+        };
+        let (start, end) = window
+            .split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("expected HH:MM-HH:MM"))?;
+        Ok(Self {
+            start: NaiveTime::parse_from_str(start.trim(), "%H:%M")?,
+            end: NaiveTime::parse_from_str(end.trim(), "%H:%M")?,
+            timezone,
+            mode,
+            bypass_severity,
+        })
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        let time = now.with_timezone(&self.timezone).time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    pub fn applies_to(&self, severity: Severity) -> bool {
+        severity < self.bypass_severity
+    }
+}
+
+// How Telegram messages over the 4096-character limit are delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongMessageMode {
+    #[default]
+    Split,
+    Truncate,
+}
+
+// A token, key or password; `Debug` leaves it out so configs can be logged.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    // Compares in constant time, so a guess cannot be refined from how long a rejection takes.
+    pub fn matches(&self, candidate: &str) -> bool {
+        !self.0.is_empty() && bool::from(self.0.as_bytes().ct_eq(candidate.as_bytes()))
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_empty() { "\"\"" } else { "[redacted]" })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceConfig {
+    pub enabled: bool,
+    pub check_interval: Duration,
+    pub telegram_api_key: Secret,
+    pub telegram_chats: Vec<TelegramChat>,
+    pub telegram_long_messages: LongMessageMode,
+    // Follow notifications that carry coordinates with a native venue message.
+    pub telegram_send_location: bool,
+    pub telegram_quiet_hours: Option<QuietHours>,
+    // Quiet hours for every channel of this service that does not set its own.
+    pub quiet_hours: Option<QuietHours>,
+    pub buymeacoffee_url: String,
+    pub disclaimer: String,
+    // Key for the upstream API, for services that need one.
+    pub api_key: Option<Secret>,
+}
+
+// How the bot receives commands sent to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateMode {
+    #[default]
+    Polling,
+    Webhook,
+    Off,
+}
+
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    pub api_key: Secret,
+    pub updates: UpdateMode,
+    // Both the last path segment of the webhook route and the expected secret token header.
+    pub webhook_secret: Secret,
+    // Public base URL of this server; when set the webhook is registered with Telegram on startup.
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscordConfig {
+    pub webhook_url: Secret,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlackConfig {
+    pub webhook_url: Secret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    None,
+    StartTls,
+    Tls,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: Secret,
+    pub tls: SmtpTls,
+    pub from: Mailbox,
+    pub to: Vec<Mailbox>,
+    pub batch: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixConfig {
+    pub homeserver_url: String,
+    pub access_token: Secret,
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookConfig {
+    pub urls: Vec<Secret>,
+    pub secret: Secret,
+    pub max_retries: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtfyConfig {
+    pub server_url: String,
+    pub topic: String,
+    pub token: Secret,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GotifyConfig {
+    pub server_url: String,
+    pub token: Secret,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelKind {
+    Discord(DiscordConfig),
+    Slack(SlackConfig),
+    Email(EmailConfig),
+    Matrix(MatrixConfig),
+    Webhook(WebhookConfig),
+    Ntfy(NtfyConfig),
+    Gotify(GotifyConfig),
+}
+
+impl ChannelKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelKind::Discord(_) => "discord",
+            ChannelKind::Slack(_) => "slack",
+            ChannelKind::Email(_) => "email",
+            ChannelKind::Matrix(_) => "matrix",
+            ChannelKind::Webhook(_) => "webhook",
+            ChannelKind::Ntfy(_) => "ntfy",
+            ChannelKind::Gotify(_) => "gotify",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelConfig {
+    pub kind: ChannelKind,
+    // Service names this channel receives; empty means all services.
+    pub services: Vec<String>,
+    pub min_severity: Severity,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl ChannelConfig {
+    fn load(settings: &Settings, prefix: &str, kind: ChannelKind) -> Self {
+        let key = format!("{}_SERVICES", prefix);
+        let services = settings.get_list(&key);
+        for name in &services {
+            if !SERVICE_NAMES.iter().any(|s| service_key(s) == service_key(name)) {
+                settings.problem(format!(
+                    "{} has unknown service '{}', expected one of: {}",
+                    key,
+                    name,
+                    SERVICE_NAMES.join(", ")
+                ));
+            }
+        }
+        Self {
+            kind,
+            services,
+            min_severity: settings.get_severity(&format!("{}_MIN_SEVERITY", prefix), Severity::default()),
+            quiet_hours: settings.get_quiet_hours(&format!("{}_QUIET_HOURS", prefix)),
+        }
+    }
+
+    pub fn accepts_service(&self, name: &str) -> bool {
+        self.services.is_empty() || self.services.iter().any(|s| service_key(s) == service_key(name))
+    }
+}
+
+// Service names as shown in notifications; channel service lists may write them in any
+// case and with or without spaces, e.g. `rocket_launch`.
+const SERVICE_NAMES: [&str; 4] = ["Earthquake", "Rocket Launch", "Space Weather", "Vulnerability"];
+
+// Short names accepted wherever a service is named by a user, e.g. `/latest quake`.
+const SERVICE_ALIASES: [(&str, &str); 4] = [
+    ("quake", "Earthquake"),
+    ("launch", "Rocket Launch"),
+    ("flare", "Space Weather"),
+    ("cve", "Vulnerability"),
+];
+
+fn service_key(name: &str) -> String {
+    name.to_lowercase().replace([' ', '_', '-'], "")
+}
+
+// Resolves a service name as written by a user, or one of its aliases.
+pub fn service_name(name: &str) -> Option<&'static str> {
+    let key = service_key(name);
+    SERVICE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map(|(_, service_name)| *service_name)
+        .or_else(|| SERVICE_NAMES.iter().find(|s| service_key(s) == key).copied())
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub earthquake: ServiceConfig,
+    pub rocket_launch: ServiceConfig,
+    pub space_weather: ServiceConfig,
+    pub vulnerability: ServiceConfig,
+    pub channels: Vec<ChannelConfig>,
+    pub bot: BotConfig,
+    // Bearer token for the admin endpoints; they are disabled without one.
+    pub admin_token: Option<Secret>,
+    // Only read at startup; changing it needs a restart.
+    pub bind_address: SocketAddr,
+}
+
+impl Config {
+    pub fn services(&self) -> [(&'static str, &ServiceConfig); 4] {
+        [
+            (SERVICE_NAMES[0], &self.earthquake),
+            (SERVICE_NAMES[1], &self.rocket_launch),
+            (SERVICE_NAMES[2], &self.space_weather),
+            (SERVICE_NAMES[3], &self.vulnerability),
+        ]
+    }
+
+    // Reads the optional config file at `path`, with the environment taking precedence.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        Self::read(path, true)
+    }
+
+    // For the command-line tools, most of which never talk to Telegram, so the bot key and
+    // chats are optional.
+    pub fn load_without_telegram(path: Option<&Path>) -> anyhow::Result<Self> {
+        Self::read(path, false)
+    }
+
+    fn read(path: Option<&Path>, telegram: bool) -> anyhow::Result<Self> {
+        Self::from_settings(Settings::load(path)?, telegram)
+    }
+
+    fn from_settings(settings: Settings, telegram: bool) -> anyhow::Result<Self> {
+
+        let telegram_api_key = Secret::from(match telegram {
+            true => settings.get_required("TELEGRAM_API_KEY", "to send notifications"),
+            false => settings.get_optional("TELEGRAM_API_KEY").unwrap_or_default(),
+        });
+        let buymeacoffee_url = settings.get_url("BUYMEACOFFEE_URL").unwrap_or_default();
+        let telegram_min_severity = settings.get_severity("TELEGRAM_MIN_SEVERITY", Severity::default());
+        let telegram_quiet_hours = settings.get_quiet_hours("TELEGRAM_QUIET_HOURS");
+        let telegram_send_location = settings.get_bool("TELEGRAM_SEND_LOCATION", false);
+        let telegram_long_messages = match settings.get_choice("TELEGRAM_LONG_MESSAGES", &["split", "truncate"]) {
+            "truncate" => LongMessageMode::Truncate,
+            _ => LongMessageMode::Split,
+        };
+        let default_chats = settings.get_telegram_chats("TELEGRAM_CHAT_ID", &[], telegram_min_severity);
+        let service = |prefix: &str, default_interval_secs: u64, api_key: Option<&str>| {
+            let enabled = settings.get_bool(&format!("{}_ENABLED", prefix), true);
+            let key = format!("{}_TELEGRAM_CHATS", prefix);
+            let telegram_chats = settings.get_telegram_chats(&key, &default_chats, telegram_min_severity);
+            if telegram && enabled && telegram_chats.is_empty() {
+                settings.problem(format!("TELEGRAM_CHAT_ID or {} is required while {} is enabled", key, prefix));
+            }
+            ServiceConfig {
+                enabled,
+                check_interval: settings.get_duration(&format!("{}_INTERVAL_SECS", prefix), default_interval_secs),
+                telegram_api_key: telegram_api_key.clone(),
+                telegram_chats,
+                telegram_long_messages,
+                telegram_send_location,
+                telegram_quiet_hours: telegram_quiet_hours.clone(),
+                quiet_hours: settings
+                    .get_quiet_hours(&format!("{}_QUIET_HOURS", prefix))
+                    .or_else(|| settings.get_quiet_hours("QUIET_HOURS")),
+                buymeacoffee_url: buymeacoffee_url.clone(),
+                disclaimer: settings.get(&format!("{}_DISCLAIMER", prefix), ""),
+                api_key: api_key.and_then(|key| settings.get_optional(key)).map(Secret::from),
+            }
+        };
+
+        let updates = match settings.get_choice("TELEGRAM_UPDATES", &["polling", "webhook", "off"]) {
+            "webhook" => UpdateMode::Webhook,
+            "off" => UpdateMode::Off,
+            _ => UpdateMode::Polling,
+        };
+        let webhook_secret = Secret::from(match updates {
+            UpdateMode::Webhook if telegram => {
+                settings.get_required("TELEGRAM_WEBHOOK_SECRET", "when TELEGRAM_UPDATES is 'webhook'")
+            }
+            _ => settings.get_optional("TELEGRAM_WEBHOOK_SECRET").unwrap_or_default(),
+        });
+        // Telegram's rules for `secret_token`; anything else only fails once `setWebhook` is called.
+        let secret = webhook_secret.expose();
+        if !secret.is_empty()
+            && (secret.len() > 256 || !secret.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        {
+            settings.problem(
+                "TELEGRAM_WEBHOOK_SECRET must be 1 to 256 characters from A-Z, a-z, 0-9, '_' and '-'".to_string(),
+            );
+        }
+        let bot = BotConfig {
+            api_key: telegram_api_key.clone(),
+            updates,
+            webhook_secret,
+            webhook_url: settings.get_url("TELEGRAM_WEBHOOK_URL"),
+        };
+
+        let mut channels = Vec::new();
+        if let Some(webhook_url) = settings.get_url("DISCORD_WEBHOOK_URL") {
+            channels.push(ChannelConfig::load(&settings, "DISCORD", ChannelKind::Discord(DiscordConfig { webhook_url: webhook_url.into() })));
+        }
+        if let Some(webhook_url) = settings.get_url("SLACK_WEBHOOK_URL") {
+            channels.push(ChannelConfig::load(&settings, "SLACK", ChannelKind::Slack(SlackConfig { webhook_url: webhook_url.into() })));
+        }
+        if let Some(smtp_host) = settings.get_optional("SMTP_HOST") {
+            let tls = match settings.get_choice("SMTP_TLS", &["starttls", "tls", "none"]) {
+                "none" => SmtpTls::None,
+                "tls" => SmtpTls::Tls,
+                _ => SmtpTls::StartTls,
+            };
+            let default_port = match tls {
+                SmtpTls::None => 25,
+                SmtpTls::StartTls => 587,
+                SmtpTls::Tls => 465,
+            };
+            if settings.get_list("EMAIL_TO").is_empty() {
+                settings.problem("EMAIL_TO is required when SMTP_HOST is set".to_string());
+            }
+            let from = settings.get_parsed("EMAIL_FROM", "automatex-notifier@localhost".parse().expect("valid default address"));
+            let email = EmailConfig {
+                smtp_host,
+                smtp_port: settings.get_parsed("SMTP_PORT", default_port),
+                smtp_username: settings.get_optional("SMTP_USERNAME").unwrap_or_default(),
+                smtp_password: Secret::from(settings.get_optional("SMTP_PASSWORD").unwrap_or_default()),
+                tls,
+                from,
+                to: settings.get_mailboxes("EMAIL_TO"),
+                batch: settings.get_bool("EMAIL_BATCH", false),
+            };
+            channels.push(ChannelConfig::load(&settings, "EMAIL", ChannelKind::Email(email)));
+        }
+        if let Some(homeserver_url) = settings.get_url("MATRIX_HOMESERVER_URL") {
+            let matrix = MatrixConfig {
+                homeserver_url,
+                access_token: Secret::from(settings.get_required("MATRIX_ACCESS_TOKEN", "when MATRIX_HOMESERVER_URL is set")),
+                room_id: settings.get_required("MATRIX_ROOM_ID", "when MATRIX_HOMESERVER_URL is set"),
+            };
+            channels.push(ChannelConfig::load(&settings, "MATRIX", ChannelKind::Matrix(matrix)));
+        }
+        let webhook_urls = settings.get_urls("WEBHOOK_URLS");
+        if !webhook_urls.is_empty() {
+            let webhook = WebhookConfig {
+                urls: webhook_urls.into_iter().map(Secret::from).collect(),
+                secret: Secret::from(settings.get_optional("WEBHOOK_SECRET").unwrap_or_default()),
+                max_retries: settings.get_parsed("WEBHOOK_MAX_RETRIES", 3),
+            };
+            channels.push(ChannelConfig::load(&settings, "WEBHOOK", ChannelKind::Webhook(webhook)));
+        }
+        if let Some(topic) = settings.get_optional("NTFY_TOPIC") {
+            let ntfy = NtfyConfig {
+                server_url: settings.get_url("NTFY_SERVER_URL").unwrap_or_else(|| "https://ntfy.sh".to_string()),
+                topic,
+                token: Secret::from(settings.get_optional("NTFY_TOKEN").unwrap_or_default()),
+            };
+            channels.push(ChannelConfig::load(&settings, "NTFY", ChannelKind::Ntfy(ntfy)));
+        }
+        if let Some(server_url) = settings.get_url("GOTIFY_SERVER_URL") {
+            let gotify = GotifyConfig {
+                server_url,
+                token: Secret::from(settings.get_required("GOTIFY_TOKEN", "when GOTIFY_SERVER_URL is set")),
+            };
+            channels.push(ChannelConfig::load(&settings, "GOTIFY", ChannelKind::Gotify(gotify)));
+        }
+
+        let config = Config {
+            earthquake: service("EARTHQUAKE", 5 * 60, None),
+            rocket_launch: service("ROCKETLAUNCH", 15 * 60, None),
+            space_weather: service("SPACEWEATHER", 30 * 60, Some("NASA_API_KEY")),
+            vulnerability: service("VULNERABILITY", 60 * 60, None),
+            channels,
+            bot,
+            admin_token: settings.get_optional("ADMIN_TOKEN").map(Secret::from),
+            bind_address: settings.get_parsed("BIND_ADDRESS", SocketAddr::from(([0, 0, 0, 0], 8010))),
+        };
+
+        let problems = settings.problems.into_inner();
+        if !problems.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} configuration problem(s):\n  - {}",
+                problems.len(),
+                problems.join("\n  - ")
+            ));
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn settings(env: &[(&str, &str)], file: &str) -> Settings {
+        Settings {
+            env: env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            file: toml::from_str::<file::File>(file).unwrap().into_vars(),
+            problems: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn file_keys_map_to_environment_variables() {
+        let vars = toml::from_str::<file::File>(
+            r#"
+            bind_address = "127.0.0.1:9000"
+            [telegram]
+            chats = ["-100", "@alerts@major"]
+            send_location = true
+            [services.earthquake]
+            interval_secs = 60
+            [services.space_weather]
+            api_key = "nasa"
+            [channels.ntfy]
+            topic = "alerts"
+            services = ["Earthquake", "Vulnerability"]
+            "#,
+        )
+        .unwrap()
+        .into_vars();
+
+        assert_eq!(vars["BIND_ADDRESS"], "127.0.0.1:9000");
+        assert_eq!(vars["TELEGRAM_CHAT_ID"], "-100,@alerts@major");
+        assert_eq!(vars["TELEGRAM_SEND_LOCATION"], "true");
+        assert_eq!(vars["EARTHQUAKE_INTERVAL_SECS"], "60");
+        assert_eq!(vars["NASA_API_KEY"], "nasa");
+        assert_eq!(vars["NTFY_TOPIC"], "alerts");
+        assert_eq!(vars["NTFY_SERVICES"], "Earthquake,Vulnerability");
+        assert!(!vars.contains_key("TELEGRAM_MIN_SEVERITY"));
+    }
+
+    #[test]
+    fn environment_takes_precedence_over_file() {
+        let secret = std::env::temp_dir().join(format!("automatex-notifier-test-{}", std::process::id()));
+        std::fs::write(&secret, "from-secret-file\n").unwrap();
+        let settings = settings(
+            &[
+                ("TELEGRAM_MIN_SEVERITY", "major"),
+                ("TELEGRAM_UPDATES", ""),
+                ("TELEGRAM_API_KEY_FILE", secret.to_str().unwrap()),
+                ("NTFY_TOKEN", "from-env"),
+                ("NTFY_TOKEN_FILE", secret.to_str().unwrap()),
+            ],
+            r#"
+            [telegram]
+            api_key = "from-file"
+            min_severity = "minor"
+            updates = "webhook"
+            long_messages = "truncate"
+            [channels.ntfy]
+            topic = "alerts"
+            token = "from-file"
+            "#,
+        );
+
+        assert_eq!(settings.get_optional("TELEGRAM_MIN_SEVERITY").as_deref(), Some("major"));
+        // An empty variable counts as unset.
+        assert_eq!(settings.get_optional("TELEGRAM_UPDATES").as_deref(), Some("webhook"));
+        assert_eq!(settings.get_optional("TELEGRAM_LONG_MESSAGES").as_deref(), Some("truncate"));
+        assert_eq!(settings.get_optional("TELEGRAM_API_KEY").as_deref(), Some("from-secret-file"));
+        assert_eq!(settings.get_optional("NTFY_TOKEN").as_deref(), Some("from-env"));
+        assert_eq!(settings.get_optional("QUIET_HOURS"), None);
+        assert!(settings.problems.borrow().is_empty());
+        std::fs::remove_file(&secret).unwrap();
+    }
+
+    #[test]
+    fn file_keys_hidden_by_the_environment() {
+        let settings = settings(
+            &[("TELEGRAM_MIN_SEVERITY", "major"), ("TELEGRAM_UPDATES", ""), ("NTFY_TOKEN_FILE", "/run/secrets/ntfy")],
+            r#"
+            [telegram]
+            min_severity = "minor"
+            updates = "webhook"
+            [channels.ntfy]
+            topic = "alerts"
+            token = "from-file"
+            "#,
+        );
+        assert_eq!(settings.hidden_file_keys(), ["NTFY_TOKEN", "TELEGRAM_MIN_SEVERITY"]);
+    }
+
+    // Anything uncommented in the example would silently hide the config file's value.
+    #[test]
+    fn env_example_only_sets_credentials() {
+        let allowed = ["TELEGRAM_API_KEY", "TELEGRAM_CHAT_ID", "NASA_API_KEY"];
+        for line in include_str!("../../.env.example").lines() {
+            let Some((key, value)) = line.split_once('=') else { continue };
+            if line.starts_with('#') || value == "\"\"" {
+                continue;
+            }
+            assert!(allowed.contains(&key), "{} is set in .env.example", key);
+        }
+    }
+
+    #[test]
+    fn telegram_chat_with_username_and_severity() {
+        let chat = TelegramChat::parse("@alerts@major", Severity::Info).unwrap();
+        assert_eq!(chat.chat_id, "@alerts");
+        assert_eq!(chat.thread_id, None);
+        assert_eq!(chat.min_severity, Severity::Major);
+
+        let chat = TelegramChat::parse("@alerts", Severity::Minor).unwrap();
+        assert_eq!(chat.chat_id, "@alerts");
+        assert_eq!(chat.min_severity, Severity::Minor);
+    }
+
+    #[test]
+    fn telegram_chat_with_thread_and_severity() {
+        let chat = TelegramChat::parse("-1001234567890/42@critical", Severity::Info).unwrap();
+        assert_eq!(chat.chat_id, "-1001234567890");
+        assert_eq!(chat.thread_id, Some(42));
+        assert_eq!(chat.min_severity, Severity::Critical);
+
+        assert!(TelegramChat::parse("@alerts@loud", Severity::Info).is_err());
+        assert!(TelegramChat::parse("@", Severity::Info).is_err());
+        assert!(TelegramChat::parse("alerts", Severity::Info).is_err());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let settings = settings(
+            &[
+                ("TELEGRAM_CHAT_ID", "-100,alerts"),
+                ("TELEGRAM_WEBHOOK_SECRET", "not secret!"),
+                ("QUIET_HOURS", "22:00-31:00"),
+                ("EMAIL_FROM", "not an address"),
+                ("EMAIL_TO", "ok@example.com,bad@"),
+            ],
+            r#"
+            buymeacoffee_url = "ftp://example.com"
+            bind_address = "localhost"
+            [telegram]
+            min_severity = "loud"
+            updates = "webhook"
+            [services.earthquake]
+            interval_secs = 0
+            [channels.email]
+            smtp_host = "smtp.example.com"
+            smtp_tls = "ssl"
+            [channels.discord]
+            webhook_url = "https://discord.com/api/webhooks/1"
+            services = ["Weather"]
+            "#,
+        );
+        let error = Config::from_settings(settings, true).unwrap_err().to_string();
+
+        let expected = [
+            "TELEGRAM_API_KEY is required",
+            "TELEGRAM_CHAT_ID has an invalid Telegram chat 'alerts'",
+            "TELEGRAM_MIN_SEVERITY 'loud' is invalid",
+            "TELEGRAM_WEBHOOK_SECRET must be 1 to 256 characters",
+            "QUIET_HOURS '22:00-31:00' is invalid",
+            "BUYMEACOFFEE_URL 'ftp://example.com' is not an http(s) URL",
+            "BIND_ADDRESS 'localhost' is invalid",
+            "EARTHQUAKE_INTERVAL_SECS must be greater than zero",
+            "SMTP_TLS 'ssl' is invalid",
+            "EMAIL_FROM 'not an address' is invalid",
+            "EMAIL_TO has an invalid address 'bad@'",
+            "DISCORD_SERVICES has unknown service 'Weather'",
+        ];
+        for problem in expected {
+            assert!(error.contains(problem), "'{}' is missing from:\n{}", problem, error);
+        }
+        // QUIET_HOURS is read for every service but reported once.
+        assert!(error.starts_with(&format!("{} configuration problem(s)", expected.len())), "{}", error);
+    }
+
+    #[test]
+    fn secrets_match_only_the_exact_value() {
+        let secret = Secret::from("s3cret-token".to_string());
+        assert!(secret.matches("s3cret-token"));
+        assert!(!secret.matches("s3cret-toke"));
+        assert!(!secret.matches("s3cret-token2"));
+        assert!(!secret.matches(""));
+        assert!(!Secret::default().matches(""));
+    }
+
+    #[test]
+    fn quiet_hours_wrap_around_midnight() {
+        let quiet = QuietHours::parse("22:00-07:00 Europe/Berlin", Tz::UTC, QuietMode::Hold, Severity::Critical).unwrap();
+        // Berlin is UTC+2 in summer.
+        let at = |h, m| Utc.with_ymd_and_hms(2024, 7, 1, h, m, 0).unwrap();
+
+        assert!(!quiet.is_active(at(19, 59)));
+        assert!(quiet.is_active(at(20, 0)));
+        assert!(quiet.is_active(at(23, 30)));
+        assert!(quiet.is_active(at(4, 59)));
+        assert!(!quiet.is_active(at(5, 0)));
+        assert!(!quiet.is_active(at(12, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let quiet = QuietHours::parse("13:00-14:30", Tz::UTC, QuietMode::Silent, Severity::Critical).unwrap();
+        let at = |h, m| Utc.with_ymd_and_hms(2024, 1, 1, h, m, 0).unwrap();
+
+        assert!(!quiet.is_active(at(12, 59)));
+        assert!(quiet.is_active(at(13, 0)));
+        assert!(!quiet.is_active(at(14, 30)));
+        assert!(!quiet.is_active(at(23, 0)));
+    }
+}
@@ -1,15 +1,20 @@
+mod bot;
+mod channels;
+mod cli;
 mod config;
+mod monitors;
+mod reload;
 mod services;
 mod state;
-mod telegram;
+mod status;
+mod subscriptions;
 
 use axum::{routing::get, Router};
-use services::{
-    earthquake, rocketlaunch, spaceweather, vulnerability, Notification, NotificationService,
-};
-use std::{net::SocketAddr, sync::Arc, time::Duration};
-use tokio::time;
-use tracing::{error, info, instrument};
+use clap::Parser;
+use cli::{Cli, Command};
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::watch;
+use tracing::{error, info};
 
 #[tokio::main]
 async fn main() {
@@ -22,116 +27,125 @@ async fn main() {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let cfg = Arc::new(config::Config::load());
+    let mut cli = Cli::parse();
+    cli.config = cli.config.filter(|path| !path.as_os_str().is_empty());
+    let command = cli.command.unwrap_or(Command::Serve);
+    let load = match command {
+        Command::Serve | Command::ValidateConfig => config::Config::load,
+        _ => config::Config::load_without_telegram,
+    };
+    let cfg = match (load(cli.config.as_deref()), &command) {
+        (Ok(cfg), _) => cfg,
+        (Err(e), Command::Serve) => {
+            error!("Could not load configuration: {:#}", e);
+            std::process::exit(1);
+        }
+        (Err(e), _) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match &command {
+        Command::Serve => {
+            serve(cli.config, Arc::new(cfg)).await;
+            Ok(())
+        }
+        Command::ValidateConfig => {
+            cli::validate_config(&cfg);
+            Ok(())
+        }
+        Command::Check { service, once } => cli::check(&cfg, service, *once).await,
+        Command::SendTest { channel } => cli::send_test(&cfg, channel).await,
+        Command::State { action } => cli::state(&cfg, action).await,
+        Command::Render { service, fixture, format } => cli::render(&cfg, service, fixture, *format).await,
+    };
+    if let Err(e) = result {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
+    }
+}
 
+async fn serve(config_path: Option<PathBuf>, cfg: Arc<config::Config>) {
     let client = reqwest::Client::new();
 
-    let mut available_services: Vec<Box<dyn NotificationService>> = Vec::new();
-    if cfg.earthquake.enabled {
-        available_services.push(Box::new(earthquake::Service::new(cfg.earthquake.clone(), client.clone())));
+    let subscriptions = subscriptions::Store::new("subscriptions.json");
+    if let Err(e) = subscriptions.load().await {
+        error!("Error loading subscriptions: {:?}", e);
     }
-    if cfg.rocket_launch.enabled {
-        available_services.push(Box::new(rocketlaunch::Service::new(cfg.rocket_launch.clone(), client.clone())));
+    if let Err(e) = channels::telegram::load_mutes().await {
+        error!("Error loading muted chats: {:?}", e);
     }
-    if cfg.space_weather.enabled {
-        available_services.push(Box::new(spaceweather::Service::new(cfg.space_weather.clone(), client.clone())));
+
+    let status = status::Registry::default();
+    let monitors = monitors::Monitors::new(config_path.clone(), client.clone(), subscriptions.clone(), status.clone());
+    monitors.apply(&cfg).await;
+    if let Some(path) = &config_path
+        && let Err(e) = reload::watch(path, monitors.clone())
+    {
+        error!("Could not watch the config file for changes: {:?}", e);
     }
-    if cfg.vulnerability.enabled {
-        available_services.push(Box::new(vulnerability::Service::new(cfg.vulnerability.clone(), client.clone())));
+    #[cfg(unix)]
+    tokio::spawn(reload::on_hangup(monitors.clone()));
+
+    let mut app = Router::new().route("/", get(handler));
+    if let Some(token) = &cfg.admin_token {
+        app = app.merge(reload::router(monitors.clone(), token.clone()));
     }
-    
-    for s in available_services {
-        tokio::spawn(start_service_monitor(Arc::from(s)));
+
+    let bot = Arc::new(bot::Bot::new(client.clone(), cfg.bot.api_key.expose().to_string(), monitors, status, subscriptions));
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let mut polling = None;
+    match cfg.bot.updates {
+        config::UpdateMode::Polling => {
+            polling = Some(tokio::spawn(bot::polling::run(bot, shutdown_rx)));
+        }
+        config::UpdateMode::Webhook => {
+            if let Some(url) = &cfg.bot.webhook_url
+                && let Err(e) = bot::webhook::register(&bot, url, cfg.bot.webhook_secret.expose()).await
+            {
+                error!("Could not register Telegram webhook: {:?}", e);
+            }
+            app = app.merge(bot::webhook::router(bot, cfg.bot.webhook_secret.clone()));
+        }
+        config::UpdateMode::Off => {}
     }
 
-    let app = Router::new().route("/", get(handler));
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8010));
+    let addr = cfg.bind_address;
     info!("Starting Automatex Notifier web server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-}
-
-async fn handler() -> &'static str {
-    "Automatex Notifier is running!"
-}
+    axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await.unwrap();
 
-#[instrument(skip(s), fields(service = %s.name()))]
-async fn start_service_monitor(s: Arc<Box<dyn NotificationService>>) {
-    info!("Initializing service");
-    if let Err(e) = s.load_state().await {
-        error!("Error loading state: {:?}", e);
-    }
-
-    let check_interval = s.get_config().check_interval;
-    let mut interval = time::interval(check_interval);
-    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
-
-    run_check(s.clone()).await;
-
-    loop {
-        interval.tick().await;
-        run_check(s.clone()).await;
+    info!("Shutting down");
+    let _ = shutdown.send(true);
+    if let Some(polling) = polling {
+        let _ = polling.await;
     }
 }
 
-#[instrument(skip(s), fields(service = %s.name()))] 
-async fn run_check(s: Arc<Box<dyn NotificationService>>) {
-    info!("Checking for new notifications...");
-    let notifications = match s.check_for_notifications().await {
-        Ok(n) => n,
-        Err(e) => {
-            error!("Error checking for notifications: {:?}", e);
-            return;
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                error!("Could not listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
         }
     };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    if notifications.is_empty() {
-        info!("No new notifications found.");
-        return;
-    }
-
-    info!("Found {} new notification(s).", notifications.len());
-
-    let cfg = s.get_config();
-    let sender = telegram::Sender::new(cfg.telegram_api_key.clone(), cfg.telegram_chat_id.clone());
-
-    for n in notifications {
-        send_single_notification(&sender, n, cfg).await;
-        time::sleep(Duration::from_secs(1)).await;
-    }
-
-    if let Err(e) = s.save_state().await {
-        error!("Error saving state: {:?}", e);
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
     }
 }
 
-async fn send_single_notification(
-    sender: &telegram::Sender,
-    n: Box<dyn Notification>,
-    cfg: &config::ServiceConfig,
-) {
-    let mut full_message = n.format_message();
-
-    if !cfg.buymeacoffee_url.is_empty() || !cfg.disclaimer.is_empty() {
-        full_message.push_str("\n\n");
-        full_message.push_str(&telegram::escape_markdown("--------------------"));
-    }
-    if !cfg.buymeacoffee_url.is_empty() {
-        full_message.push_str("\n\n*Like this service?*");
-        full_message.push_str(&format!(
-            "\n[Buy Me a Coffee ☕]({})",
-            cfg.buymeacoffee_url
-        ));
-    }
-    if !cfg.disclaimer.is_empty() {
-        full_message.push_str("\n\n");
-        full_message.push_str(&cfg.disclaimer);
-    }
-
-    if let Err(e) = sender.send(&full_message).await {
-        error!(notification_id = %n.get_unique_id(), "Failed to send notification: {:?}", e);
-    } else {
-        info!(notification_id = %n.get_unique_id(), "Successfully sent notification.");
-    }
-}
\ No newline at end of file
+async fn handler() -> &'static str {
+    "Automatex Notifier is running!"
+}
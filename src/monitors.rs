@@ -0,0 +1,226 @@
+use crate::{
+    channels::Dispatcher,
+    config::{ChannelConfig, Config, ServiceConfig},
+    services::{earthquake, rocketlaunch, spaceweather, vulnerability, Notification, NotificationService},
+    status::Registry,
+    subscriptions,
+};
+use chrono::{TimeDelta, Utc};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
+use tracing::{error, info, instrument};
+
+struct Monitor {
+    service: Arc<Box<dyn NotificationService>>,
+    // The channels the service was routed to when the monitor started.
+    channels: Vec<ChannelConfig>,
+    dispatcher: Arc<Dispatcher>,
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl Monitor {
+    // Lets a check in progress finish before the monitor ends.
+    async fn stop(self) {
+        let _ = self.stop.send(());
+        if let Err(e) = self.handle.await {
+            error!(service = self.service.name(), "Service monitor failed: {:?}", e);
+        }
+    }
+}
+
+// The running service monitors, which follow the configuration as it is reloaded.
+#[derive(Clone)]
+pub struct Monitors {
+    config_path: Option<PathBuf>,
+    client: reqwest::Client,
+    subscriptions: subscriptions::Store,
+    status: Registry,
+    running: Arc<Mutex<Vec<Monitor>>>,
+    // Serializes reloads, since `running` is not held while monitors stop.
+    applying: Arc<Mutex<()>>,
+}
+
+impl Monitors {
+    pub fn new(config_path: Option<PathBuf>, client: reqwest::Client, subscriptions: subscriptions::Store, status: Registry) -> Self {
+        Self {
+            config_path,
+            client,
+            subscriptions,
+            status,
+            running: Arc::new(Mutex::new(Vec::new())),
+            applying: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub async fn services(&self) -> Vec<Arc<Box<dyn NotificationService>>> {
+        let running = self.running.lock().await;
+        running.iter().map(|m| m.service.clone()).collect()
+    }
+
+    // Loads the configuration again and applies it; an invalid one leaves everything running as is.
+    pub async fn reload(&self, reason: &str) -> anyhow::Result<()> {
+        info!("Reloading configuration ({})", reason);
+        let config = Config::load(self.config_path.as_deref()).inspect_err(|e| {
+            error!("Keeping the current configuration: {:#}", e);
+        })?;
+        self.apply(&config).await;
+        Ok(())
+    }
+
+    // Starts monitors for newly enabled services, stops disabled ones and restarts the ones whose
+    // settings changed. Restarted monitors keep the service's state and check schedule.
+    pub async fn apply(&self, config: &Config) {
+        let _applying = self.applying.lock().await;
+
+        // Monitors that change are taken out first, so waiting for their checks to finish does
+        // not block `services` in the meantime.
+        let mut changes = Vec::new();
+        {
+            let mut running = self.running.lock().await;
+            for (name, cfg) in config.services() {
+                let channels: Vec<ChannelConfig> = config.channels.iter().filter(|c| c.accepts_service(name)).cloned().collect();
+                let old = running.iter().position(|m| m.service.name() == name);
+                match old {
+                    Some(i) if cfg.enabled && running[i].service.get_config() == cfg && running[i].channels == channels => {}
+                    _ => changes.push((name, cfg, channels, old.map(|i| running.swap_remove(i)))),
+                }
+            }
+        }
+
+        let mut started = Vec::new();
+        for (name, cfg, channels, old) in changes {
+            match old {
+                None if cfg.enabled => {
+                    let service = Arc::new(create(name, cfg.clone(), self.client.clone()));
+                    let dispatcher = self.dispatcher(config, service.as_ref().as_ref());
+                    started.push(self.spawn(service, dispatcher, channels, false));
+                }
+                None => {}
+                Some(old) if !cfg.enabled => {
+                    old.stop().await;
+                    info!(service = name, "Stopped service monitor");
+                }
+                Some(old) => {
+                    let service = Arc::new(old.service.reconfigured(cfg.clone()));
+                    let dispatcher = self.dispatcher(config, service.as_ref().as_ref());
+                    let previous_dispatcher = old.dispatcher.clone();
+                    old.stop().await;
+                    dispatcher.adopt_held(&previous_dispatcher);
+                    info!(service = name, "Restarting service monitor with new settings");
+                    started.push(self.spawn(service, dispatcher, channels, true));
+                }
+            }
+        }
+        self.running.lock().await.extend(started);
+    }
+
+    fn dispatcher(&self, config: &Config, s: &dyn NotificationService) -> Dispatcher {
+        Dispatcher::for_service(
+            config,
+            s.name(),
+            s.get_config(),
+            s.get_state_manager(),
+            &self.subscriptions,
+            &self.client,
+        )
+    }
+
+    fn spawn(&self, service: Arc<Box<dyn NotificationService>>, dispatcher: Dispatcher, channels: Vec<ChannelConfig>, resume: bool) -> Monitor {
+        let dispatcher = Arc::new(dispatcher);
+        let (stop, stopped) = oneshot::channel();
+        let handle = tokio::spawn(start_service_monitor(
+            service.clone(),
+            dispatcher.clone(),
+            self.status.clone(),
+            resume,
+            stopped,
+        ));
+        Monitor {
+            service,
+            channels,
+            dispatcher,
+            stop,
+            handle,
+        }
+    }
+}
+
+// Expects one of the names from `Config::services`.
+pub fn create(name: &str, cfg: ServiceConfig, client: reqwest::Client) -> Box<dyn NotificationService> {
+    match name {
+        "Earthquake" => Box::new(earthquake::Service::new(cfg, client)),
+        "Rocket Launch" => Box::new(rocketlaunch::Service::new(cfg, client)),
+        "Space Weather" => Box::new(spaceweather::Service::new(cfg, client)),
+        _ => Box::new(vulnerability::Service::new(cfg, client)),
+    }
+}
+
+// A resumed monitor continues the schedule of the one it replaces instead of loading the state
+// and checking right away.
+#[instrument(skip(s, dispatcher, status, resume, stopped), fields(service = %s.name()))]
+async fn start_service_monitor(
+    s: Arc<Box<dyn NotificationService>>,
+    dispatcher: Arc<Dispatcher>,
+    status: Registry,
+    resume: bool,
+    mut stopped: oneshot::Receiver<()>,
+) {
+    let check_interval = s.get_config().check_interval;
+    let first_check = if resume {
+        let last_check = status.get(s.name()).await.last_check.unwrap_or_else(Utc::now);
+        let next_check = last_check + TimeDelta::from_std(check_interval).unwrap_or_default();
+        Instant::now() + (next_check - Utc::now()).to_std().unwrap_or_default()
+    } else {
+        info!("Initializing service");
+        if let Err(e) = s.load_state().await {
+            error!("Error loading state: {:?}", e);
+        }
+        if let Err(e) = dispatcher.load_held().await {
+            error!("Error loading held notifications: {:?}", e);
+        }
+        Instant::now()
+    };
+
+    let mut interval = time::interval_at(first_check, check_interval);
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => run_check(s.clone(), &dispatcher, &status).await,
+            _ = &mut stopped => return,
+        }
+    }
+}
+
+#[instrument(skip(s, dispatcher, status), fields(service = %s.name()))]
+async fn run_check(s: Arc<Box<dyn NotificationService>>, dispatcher: &Dispatcher, status: &Registry) {
+    info!("Checking for new notifications...");
+    let mut notifications: Vec<Arc<dyn Notification>> = match s.check_for_notifications().await {
+        Ok(n) => n.into_iter().map(Arc::from).collect(),
+        Err(e) => {
+            error!("Error checking for notifications: {:?}", e);
+            status.record_error(s.name(), format!("{:#}", e)).await;
+            return;
+        }
+    };
+
+    if notifications.is_empty() {
+        info!("No new notifications found.");
+    } else {
+        info!("Found {} new notification(s).", notifications.len());
+    }
+
+    let cfg = s.get_config();
+    notifications.sort_by_key(|n| n.get_timestamp());
+    status.record_check(s.name(), notifications.last().cloned()).await;
+
+    dispatcher.dispatch(&notifications, cfg).await;
+
+    if let Err(e) = s.save_state().await {
+        error!("Error saving state: {:?}", e);
+    }
+}
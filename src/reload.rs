@@ -0,0 +1,94 @@
+use crate::config::Secret;
+use crate::monitors::Monitors;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use notify::{RecursiveMode, Watcher};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time;
+use tracing::{error, warn};
+
+// Editors often write a file in several steps, so changes are collected for a moment first.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+// Reloads the configuration whenever the config file changes. The directory is watched rather
+// than the file, because editors and config management tools replace files instead of writing them.
+pub fn watch(path: &Path, monitors: Monitors) -> anyhow::Result<()> {
+    let file_name = path.file_name().map(|n| n.to_os_string());
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => ".".into(),
+    };
+
+    let (tx, mut rx) = mpsc::channel(1);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+        Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+            if event.paths.iter().any(|p| p.file_name() == file_name.as_deref()) {
+                let _ = tx.try_send(());
+            }
+        }
+        Ok(_) => {}
+        Err(e) => error!("Error watching the config file: {:?}", e),
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    tokio::spawn(async move {
+        // Keep the watcher alive for as long as changes are handled.
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            time::sleep(SETTLE_TIME).await;
+            while rx.try_recv().is_ok() {}
+            let _ = monitors.reload("config file changed").await;
+        }
+    });
+    Ok(())
+}
+
+#[cfg(unix)]
+pub async fn on_hangup(monitors: Monitors) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!("Could not listen for SIGHUP: {:?}", e);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        let _ = monitors.reload("SIGHUP").await;
+    }
+}
+
+#[derive(Clone)]
+struct AdminState {
+    monitors: Monitors,
+    token: Secret,
+}
+
+pub fn router(monitors: Monitors, token: Secret) -> Router {
+    Router::new()
+        .route("/admin/reload", post(reload))
+        .with_state(AdminState { monitors, token })
+}
+
+async fn reload(State(state): State<AdminState>, headers: HeaderMap) -> (StatusCode, String) {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if !token.is_some_and(|token| state.token.matches(token)) {
+        warn!("Rejected admin request without a valid token.");
+        return (StatusCode::UNAUTHORIZED, "Unauthorized\n".to_string());
+    }
+
+    match state.monitors.reload("admin request").await {
+        Ok(()) => (StatusCode::OK, "Configuration reloaded\n".to_string()),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}\n", e)),
+    }
+}
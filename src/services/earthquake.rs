@@ -1,5 +1,8 @@
-use super::{Notification, NotificationService};
-use crate::{config::ServiceConfig, state::Manager, telegram};
+use super::{Field, GeoPoint, Link, Message, Notification, NotificationService, Severity};
+use crate::{
+    config::ServiceConfig,
+    state::{Manager, Observation},
+};
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::time::Duration;
@@ -30,31 +33,41 @@ pub struct EarthquakeNotification {
     url: String,
     latitude: f64,
     longitude: f64,
+    updated: bool,
 }
 
 impl Notification for EarthquakeNotification {
     fn get_unique_id(&self) -> &str { &self.id }
     fn get_timestamp(&self) -> i64 { self.time }
-    fn format_message(&self) -> String {
-        let title = "🌍 *Earthquake Report* 🌍";
-        let time_str = chrono::DateTime::from_timestamp(self.time, 0)
-            .map(|t| t.format("%c").to_string())
-            .unwrap_or_else(|| "N/A".to_string());
-        
-        let gmaps_url = format!(
-            "https://www.google.com/maps/place/{},{}/@{:.4},{:.4},5z",
-            self.latitude, self.longitude, self.latitude, self.longitude
-        );
-
-        format!(
-            "{}\n\n*Magnitude:* {}\n*Location:* {}\n*Time:* {}\n*Map:* [Google Maps]({}) \\| [Details on USGS]({})",
-            title,
-            telegram::escape_markdown(&format!("{:.2}", self.magnitude)),
-            telegram::escape_markdown(&self.location),
-            telegram::escape_markdown(&time_str),
-            gmaps_url,
-            self.url
-        )
+    fn score(&self) -> Option<f64> { Some(self.magnitude) }
+    fn message(&self) -> Message {
+        let severity = if self.magnitude >= 7.0 {
+            Severity::Critical
+        } else if self.magnitude >= 6.0 {
+            Severity::Major
+        } else if self.magnitude >= 5.0 {
+            Severity::Minor
+        } else {
+            Severity::Info
+        };
+
+        Message {
+            title: "🌍 Earthquake Report 🌍".to_string(),
+            severity,
+            fields: vec![
+                Field::number("magnitude", "Magnitude", self.magnitude, 2),
+                Field::text("location", "Location", self.location.clone()),
+                Field::time("time", "Time", self.time),
+            ],
+            links: vec![Link::new("Details on USGS", self.url.clone())],
+            geo: Some(GeoPoint {
+                latitude: self.latitude,
+                longitude: self.longitude,
+                label: self.location.clone(),
+            }),
+            tags: vec!["earthquake".to_string()],
+            updated: self.updated,
+        }
     }
 }
 
@@ -63,8 +76,22 @@ impl NotificationService for Service {
     fn name(&self) -> &str { "Earthquake" }
     fn get_config(&self) -> &ServiceConfig { &self.config }
     fn get_state_manager(&self) -> &Manager { &self.state }
+    fn score_name(&self) -> Option<&str> { Some("magnitude") }
+
+    fn reconfigured(&self, config: ServiceConfig) -> Box<dyn NotificationService> {
+        Box::new(Service {
+            state: self.state.clone(),
+            config,
+            client: self.client.clone(),
+        })
+    }
 
     async fn check_for_notifications(&self) -> anyhow::Result<Vec<Box<dyn Notification>>> {
+        let res = self.client.get("https://earthquake.usgs.gov/earthquakes/feed/v1.0/summary/4.5_day.geojson").send().await?.json().await?;
+        self.notifications_from(res).await
+    }
+
+    async fn notifications_from(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>> {
         #[derive(Deserialize)]
         struct UsgsFeature {
             id: String,
@@ -87,25 +114,55 @@ impl NotificationService for Service {
             features: Vec<UsgsFeature>,
         }
 
-        let res: UsgsResponse = self.client.get("https://earthquake.usgs.gov/earthquakes/feed/v1.0/summary/4.5_day.geojson").send().await?.json().await?;
+        let res: UsgsResponse = serde_json::from_value(response)?;
 
         let mut notifications: Vec<Box<dyn Notification>> = Vec::new();
         for feature in res.features {
-            if !self.state.is_seen(&feature.id).await {
-                let event_time_secs = feature.properties.time / 1000;
-                let notif = Box::new(EarthquakeNotification {
-                    id: feature.id.clone(),
-                    magnitude: feature.properties.mag,
-                    location: feature.properties.place,
-                    time: event_time_secs,
-                    url: feature.properties.url,
-                    latitude: feature.geometry.coordinates[1],
-                    longitude: feature.geometry.coordinates[0],
-                });
-                notifications.push(notif);
-                self.state.add(feature.id, event_time_secs).await;
-            }
+            let event_time_secs = feature.properties.time / 1000;
+            let revision = feature.properties.mag.to_string();
+            let updated = match self.state.observe(&feature.id, event_time_secs, revision, magnitude_revised).await {
+                Observation::New => false,
+                Observation::Revised => true,
+                Observation::Unchanged => continue,
+            };
+
+            let notif = Box::new(EarthquakeNotification {
+                id: feature.id,
+                magnitude: feature.properties.mag,
+                location: feature.properties.place,
+                time: event_time_secs,
+                url: feature.properties.url,
+                latitude: feature.geometry.coordinates[1],
+                longitude: feature.geometry.coordinates[0],
+                updated,
+            });
+            notifications.push(notif);
         }
         Ok(notifications)
     }
-}
\ No newline at end of file
+}
+
+// USGS revises magnitudes as more data comes in; only a change of 0.1 or more from the magnitude
+// last reported is worth an update. The tolerance absorbs float error, e.g. in 5.05 - 4.95.
+fn magnitude_revised(previous: &str, current: &str) -> bool {
+    match (previous.parse::<f64>(), current.parse::<f64>()) {
+        (Ok(previous), Ok(current)) => (current - previous).abs() >= 0.1 - 1e-9,
+        _ => previous != current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magnitude_revisions_of_at_least_a_tenth() {
+        assert!(!magnitude_revised("4.949", "4.951"));
+        assert!(!magnitude_revised("4.95", "5.04"));
+        assert!(magnitude_revised("4.95", "5.05"));
+        assert!(magnitude_revised("5.3", "5.2"));
+        // State saved by older versions holds the rounded magnitude.
+        assert!(magnitude_revised("4.9", "5"));
+        assert!(!magnitude_revised("4.9", "4.97"));
+    }
+}
@@ -1,17 +1,188 @@
 use crate::config::ServiceConfig;
 use crate::state::Manager;
 use async_trait::async_trait;
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
 
 pub mod earthquake;
 pub mod rocketlaunch;
 pub mod spaceweather;
 pub mod vulnerability;
 
+// Ordered from least to most urgent, so channels can route with `severity >= min_severity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Minor,
+    Major,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "minor" => Ok(Severity::Minor),
+            "major" => Ok(Severity::Major),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(anyhow::anyhow!("Unknown severity '{}', expected info, minor, major or critical", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FieldValue {
+    Text(String),
+    Number { value: f64, precision: usize },
+    Time(i64),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(s) => f.write_str(s),
+            FieldValue::Number { value, precision } => write!(f, "{:.*}", precision, value),
+            FieldValue::Time(ts) => f.write_str(&format_time(*ts)),
+        }
+    }
+}
+
+// Names are borrowed from the services, and owned once a message is read back from disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Field {
+    // Stable machine-readable identifier, e.g. for webhook payloads.
+    pub key: Cow<'static, str>,
+    pub name: Cow<'static, str>,
+    pub value: FieldValue,
+}
+
+impl Field {
+    pub fn is_long(&self) -> bool {
+        let value = self.value.to_string();
+        value.contains('\n') || value.chars().count() > 80
+    }
+
+    pub fn text(key: &'static str, name: &'static str, value: impl Into<String>) -> Self {
+        Self { key: key.into(), name: name.into(), value: FieldValue::Text(value.into()) }
+    }
+
+    pub fn number(key: &'static str, name: &'static str, value: f64, precision: usize) -> Self {
+        Self { key: key.into(), name: name.into(), value: FieldValue::Number { value, precision } }
+    }
+
+    pub fn time(key: &'static str, name: &'static str, timestamp: i64) -> Self {
+        Self { key: key.into(), name: name.into(), value: FieldValue::Time(timestamp) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    pub label: String,
+    pub url: String,
+}
+
+impl Link {
+    pub fn new(label: &str, url: impl Into<String>) -> Self {
+        Self { label: label.to_string(), url: url.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+    // Human-readable place name, e.g. "10 km SSW of Hualien City, Taiwan".
+    pub label: String,
+}
+
+impl GeoPoint {
+    pub fn map_url(&self) -> String {
+        format!(
+            "https://www.google.com/maps/place/{},{}/@{:.4},{:.4},5z",
+            self.latitude, self.longitude, self.latitude, self.longitude
+        )
+    }
+}
+
+// Channel-agnostic representation of a notification; each channel decides how to render it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub title: String,
+    pub severity: Severity,
+    pub fields: Vec<Field>,
+    // The first link is the primary "details" link.
+    pub links: Vec<Link>,
+    pub geo: Option<GeoPoint>,
+    pub tags: Vec<String>,
+    // Set when the upstream record changed after it was first notified.
+    pub updated: bool,
+}
+
+impl Message {
+    pub fn heading(&self) -> String {
+        if self.updated {
+            format!("🔄 Updated: {}", self.title)
+        } else {
+            self.title.clone()
+        }
+    }
+
+    pub fn primary_url(&self) -> Option<&str> {
+        self.links.first().map(|l| l.url.as_str())
+    }
+
+    // Links plus a map link for channels that cannot show the location natively.
+    pub fn display_links(&self) -> Vec<Link> {
+        let mut links = self.links.clone();
+        if let Some(geo) = &self.geo {
+            links.push(Link::new("Google Maps", geo.map_url()));
+        }
+        links
+    }
+}
+
 pub trait Notification: Debug + Send + Sync {
     fn get_unique_id(&self) -> &str;
     fn get_timestamp(&self) -> i64;
-    fn format_message(&self) -> String;
+    fn message(&self) -> Message;
+
+    fn severity(&self) -> Severity {
+        self.message().severity
+    }
+
+    // The value subscribers can set a minimum for, e.g. a magnitude or CVSS score.
+    fn score(&self) -> Option<f64> {
+        None
+    }
+}
+
+pub fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%c").to_string())
+        .unwrap_or_else(|| "N/A".to_string())
 }
 
 #[async_trait]
@@ -20,8 +191,32 @@ pub trait NotificationService: Send + Sync {
     fn get_config(&self) -> &ServiceConfig;
     fn get_state_manager(&self) -> &Manager;
 
+    // A copy of the service with new settings that shares its state, used when the
+    // configuration is reloaded.
+    fn reconfigured(&self, config: ServiceConfig) -> Box<dyn NotificationService>;
+
+    // What `Notification::score` measures for this service, if anything.
+    fn score_name(&self) -> Option<&str> {
+        None
+    }
+
     async fn check_for_notifications(&self) -> anyhow::Result<Vec<Box<dyn Notification>>>;
 
+    // The notifications for a response of the upstream API, as `check_for_notifications` finds
+    // them after fetching it. Lets responses saved as fixtures be rendered offline.
+    async fn notifications_from(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>>;
+
+    // The notifications for a response saved as a fixture, without filters measured against
+    // the current time.
+    async fn fixture_notifications(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>> {
+        self.notifications_from(response).await
+    }
+
+    // The next scheduled event, for services that know about upcoming ones.
+    async fn next_event(&self) -> anyhow::Result<Option<Box<dyn Notification>>> {
+        Ok(None)
+    }
+
     async fn load_state(&self) -> anyhow::Result<()> {
         self.get_state_manager().load().await
     }
@@ -1,5 +1,8 @@
-use super::{Notification, NotificationService};
-use crate::{config::ServiceConfig, state::Manager, telegram};
+use super::{Field, Link, Message, Notification, NotificationService, Severity};
+use crate::{
+    config::ServiceConfig,
+    state::{Manager, Observation},
+};
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::time::Duration;
@@ -19,6 +22,11 @@ impl Service {
             client, 
         }
     }
+
+    async fn fetch_upcoming(&self, query: &str) -> anyhow::Result<serde_json::Value> {
+        let url = format!("https://ll.thespacedevs.com/2.2.0/launch/upcoming/?{}", query);
+        Ok(self.client.get(&url).send().await?.json().await?)
+    }
 }
 
 #[derive(Debug)]
@@ -29,30 +37,103 @@ pub struct RocketLaunchNotification {
     vehicle: String,
     launch_time: i64,
     watch_url: Option<String>,
+    // Fixed when the launch is found, so routing, quiet hours and rendering agree on it
+    // even while the notification is held.
+    severity: Severity,
+    updated: bool,
 }
 
 impl Notification for RocketLaunchNotification {
     fn get_unique_id(&self) -> &str { &self.id }
     fn get_timestamp(&self) -> i64 { self.launch_time }
-    fn format_message(&self) -> String {
-        let title = "🚀 *Rocket Launch Alert* 🚀";
-        let time_str = chrono::DateTime::from_timestamp(self.launch_time, 0)
-            .map(|t| t.format("%c").to_string())
-            .unwrap_or_else(|| "N/A".to_string());
-        
-        let mut msg = format!(
-            "{}\n\n*Mission:* {}\n*Agency:* {}\n*Vehicle:* {}\n*Launch Time:* {}",
-            title,
-            telegram::escape_markdown(&self.name),
-            telegram::escape_markdown(&self.agency),
-            telegram::escape_markdown(&self.vehicle),
-            telegram::escape_markdown(&time_str)
-        );
-
-        if let Some(url) = &self.watch_url {
-            msg.push_str(&format!("\n*Watch Live:* [Click Here]({})", url));
+    fn message(&self) -> Message {
+        Message {
+            title: "🚀 Rocket Launch Alert 🚀".to_string(),
+            severity: self.severity,
+            fields: vec![
+                Field::text("mission", "Mission", self.name.clone()),
+                Field::text("agency", "Agency", self.agency.clone()),
+                Field::text("vehicle", "Vehicle", self.vehicle.clone()),
+                Field::time("launch_time", "Launch Time", self.launch_time),
+            ],
+            links: self
+                .watch_url
+                .iter()
+                .map(|url| Link::new("Watch Live", url.clone()))
+                .collect(),
+            geo: None,
+            tags: vec!["rocket".to_string(), "launch".to_string()],
+            updated: self.updated,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LaunchResult {
+    id: String,
+    name: String,
+    net: String, 
+    launch_service_provider: LaunchServiceProvider,
+    rocket: Rocket,
+    #[serde(rename = "vidURLs", default)]
+    vid_urls: Vec<VidURL>,
+}
+
+#[derive(Deserialize)]
+struct LaunchServiceProvider {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Rocket {
+    configuration: RocketConfiguration,
+}
+
+#[derive(Deserialize)]
+struct RocketConfiguration {
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+struct VidURL {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct LaunchResponse {
+    results: Vec<LaunchResult>,
+}
+
+impl LaunchResult {
+    fn launch_time(&self) -> Option<i64> {
+        match chrono::DateTime::parse_from_rfc3339(&self.net) {
+            Ok(t) => Some(t.timestamp()),
+            Err(e) => {
+                warn!(launch_id = %self.id, "Could not parse launch time: {}", e);
+                None
+            }
+        }
+    }
+
+    fn into_notification(self, launch_time: i64, updated: bool) -> RocketLaunchNotification {
+        let time_until_launch = launch_time - chrono::Utc::now().timestamp();
+        let severity = if time_until_launch <= 15 * 60 {
+            Severity::Major
+        } else if time_until_launch <= 3600 {
+            Severity::Minor
+        } else {
+            Severity::Info
+        };
+        RocketLaunchNotification {
+            id: self.id,
+            name: self.name,
+            agency: self.launch_service_provider.name,
+            vehicle: self.rocket.configuration.full_name,
+            launch_time,
+            watch_url: self.vid_urls.first().map(|v| v.url.clone()),
+            severity,
+            updated,
         }
-        msg
     }
 }
 
@@ -62,78 +143,73 @@ impl NotificationService for Service {
     fn get_config(&self) -> &ServiceConfig { &self.config }
     fn get_state_manager(&self) -> &Manager { &self.state }
 
-    async fn check_for_notifications(&self) -> anyhow::Result<Vec<Box<dyn Notification>>> {
-        #[derive(Deserialize)]
-        struct LaunchResult {
-            id: String,
-            name: String,
-            net: String, 
-            launch_service_provider: LaunchServiceProvider,
-            rocket: Rocket,
-            #[serde(rename = "vidURLs", default)]
-            vid_urls: Vec<VidURL>,
-        }
-
-        #[derive(Deserialize)]
-        struct LaunchServiceProvider {
-            name: String,
-        }
-
-        #[derive(Deserialize)]
-        struct Rocket {
-            configuration: RocketConfiguration,
-        }
-        
-        #[derive(Deserialize)]
-        struct RocketConfiguration {
-            full_name: String,
-        }
+    fn reconfigured(&self, config: ServiceConfig) -> Box<dyn NotificationService> {
+        Box::new(Service {
+            state: self.state.clone(),
+            config,
+            client: self.client.clone(),
+        })
+    }
 
-        #[derive(Deserialize)]
-        struct VidURL {
-            url: String,
-        }
+    async fn check_for_notifications(&self) -> anyhow::Result<Vec<Box<dyn Notification>>> {
+        let window_end = (chrono::Utc::now() + chrono::Duration::hours(24)).to_rfc3339();
+        let res = self.fetch_upcoming(&format!("limit=10&window_end={}", window_end)).await?;
+        self.notifications_from(res).await
+    }
 
-        #[derive(Deserialize)]
-        struct LaunchResponse {
-            results: Vec<LaunchResult>,
-        }
-        
+    async fn notifications_from(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>> {
         let now = chrono::Utc::now();
-        let window_end = (now + chrono::Duration::hours(24)).to_rfc3339();
-        let url = format!(
-            "https://ll.thespacedevs.com/2.2.0/launch/upcoming/?limit=10&window_end={}",
-            window_end
-        );
-
-        let res: LaunchResponse = self.client.get(&url).send().await?.json().await?;
+        let res: LaunchResponse = serde_json::from_value(response)?;
 
         let mut notifications: Vec<Box<dyn Notification>> = Vec::new();
         for result in res.results {
-            let launch_time = match chrono::DateTime::parse_from_rfc3339(&result.net) {
-                Ok(t) => t,
-                Err(e) => {
-                    warn!(launch_id = %result.id, "Could not parse launch time: {}", e);
+            let Some(launch_time_secs) = result.launch_time() else {
+                continue;
+            };
+            let time_until_launch = chrono::Duration::seconds(launch_time_secs - now.timestamp());
+
+            // Launches already announced are re-sent as updates whenever their NET shifts.
+            let updated = if self.state.is_seen(&result.id).await {
+                let observation = self
+                    .state
+                    .observe(&result.id, launch_time_secs, launch_time_secs.to_string(), |a, b| a != b)
+                    .await;
+                if observation != Observation::Revised {
                     continue;
                 }
+                true
+            } else if time_until_launch < chrono::Duration::from_std(self.config.check_interval + Duration::from_secs(60)).unwrap() {
+                self.state.observe(&result.id, launch_time_secs, launch_time_secs.to_string(), |a, b| a != b).await;
+                false
+            } else {
+                continue;
             };
-            
-            let time_until_launch = launch_time.signed_duration_since(now);
-            
-            if !self.state.is_seen(&result.id).await && time_until_launch < chrono::Duration::from_std(self.config.check_interval + Duration::from_secs(60)).unwrap() {
-                let launch_time_secs = launch_time.timestamp();
-                let notif = Box::new(RocketLaunchNotification {
-                    id: result.id.clone(),
-                    name: result.name,
-                    agency: result.launch_service_provider.name,
-                    vehicle: result.rocket.configuration.full_name,
-                    launch_time: launch_time_secs,
-                    watch_url: result.vid_urls.first().map(|v| v.url.clone()),
-                });
-                notifications.push(notif);
-                self.state.add(result.id, launch_time_secs).await;
-            }
+
+            notifications.push(Box::new(result.into_notification(launch_time_secs, updated)));
         }
         Ok(notifications)
     }
-}
\ No newline at end of file
+
+    // Every launch in the response: a saved one is soon past the window of launches due before
+    // the next check, which is measured against the current time.
+    async fn fixture_notifications(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>> {
+        let res: LaunchResponse = serde_json::from_value(response)?;
+        Ok(res
+            .results
+            .into_iter()
+            .filter_map(|result| {
+                let launch_time = result.launch_time()?;
+                Some(Box::new(result.into_notification(launch_time, false)) as Box<dyn Notification>)
+            })
+            .collect())
+    }
+
+    async fn next_event(&self) -> anyhow::Result<Option<Box<dyn Notification>>> {
+        let res: LaunchResponse = serde_json::from_value(self.fetch_upcoming("limit=1").await?)?;
+        let Some(result) = res.results.into_iter().next() else {
+            return Ok(None);
+        };
+        let launch_time = chrono::DateTime::parse_from_rfc3339(&result.net)?.timestamp();
+        Ok(Some(Box::new(result.into_notification(launch_time, false))))
+    }
+}
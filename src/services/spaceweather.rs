@@ -1,5 +1,8 @@
-use super::{Notification, NotificationService};
-use crate::{config::ServiceConfig, state::Manager, telegram};
+use super::{Field, Link, Message, Notification, NotificationService, Severity};
+use crate::{
+    config::{Secret, ServiceConfig},
+    state::Manager,
+};
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::time::Duration;
@@ -33,20 +36,33 @@ pub struct SpaceWeatherNotification {
 impl Notification for SpaceWeatherNotification {
     fn get_unique_id(&self) -> &str { &self.id }
     fn get_timestamp(&self) -> i64 { self.time }
-    fn format_message(&self) -> String {
-        let title = "☀️ *Space Weather Alert* ☀️";
-        let time_str = chrono::DateTime::from_timestamp(self.time, 0)
-            .map(|t| t.format("%c").to_string())
-            .unwrap_or_else(|| "N/A".to_string());
-        
-        format!(
-            "{}\n\n*Event:* {}\n*Class:* {}\n*Time:* {}\n*Potential Impact:* Strong HF radio blackouts on Earth's sunlit side, increased aurora chances\\.\n*Details:* [NASA DONKI]({})",
-            title,
-            telegram::escape_markdown(&self.event_type),
-            telegram::escape_markdown(&self.class_type),
-            telegram::escape_markdown(&time_str),
-            self.url
-        )
+    fn message(&self) -> Message {
+        let magnitude = self.class_type.get(1..).and_then(|m| m.parse::<f64>().ok()).unwrap_or(0.0);
+        let severity = match self.class_type.chars().next() {
+            Some('X') => Severity::Critical,
+            Some('M') if magnitude >= 5.0 => Severity::Major,
+            Some('M') => Severity::Minor,
+            _ => Severity::Info,
+        };
+
+        Message {
+            title: "☀️ Space Weather Alert ☀️".to_string(),
+            severity,
+            fields: vec![
+                Field::text("event", "Event", self.event_type.clone()),
+                Field::text("class", "Class", self.class_type.clone()),
+                Field::time("time", "Time", self.time),
+                Field::text(
+                    "potential_impact",
+                    "Potential Impact",
+                    "Strong HF radio blackouts on Earth's sunlit side, increased aurora chances.",
+                ),
+            ],
+            links: vec![Link::new("NASA DONKI", self.url.clone())],
+            geo: None,
+            tags: vec!["solar-flare".to_string(), self.class_type.to_lowercase()],
+            updated: false,
+        }
     }
 }
 
@@ -56,7 +72,30 @@ impl NotificationService for Service {
     fn get_config(&self) -> &ServiceConfig { &self.config }
     fn get_state_manager(&self) -> &Manager { &self.state }
 
+    fn reconfigured(&self, config: ServiceConfig) -> Box<dyn NotificationService> {
+        Box::new(Service {
+            state: self.state.clone(),
+            config,
+            client: self.client.clone(),
+        })
+    }
+
     async fn check_for_notifications(&self) -> anyhow::Result<Vec<Box<dyn Notification>>> {
+        let api_key = self.config.api_key.as_ref().map_or("DEMO_KEY", Secret::expose);
+        let start_date = (chrono::Utc::now() - chrono::Duration::hours(24)).format("%Y-%m-%d");
+        let url = format!("https://api.nasa.gov/DONKI/FLR?startDate={}&api_key={}", start_date, api_key);
+        
+        // Errors leave out the URL, which carries the API key.
+        let res = self.client.get(&url).send().await.map_err(reqwest::Error::without_url)?;
+        
+        if !res.status().is_success() {
+             return Err(anyhow::anyhow!("NASA API returned status {}", res.status()));
+        }
+
+        self.notifications_from(res.json().await.map_err(reqwest::Error::without_url)?).await
+    }
+
+    async fn notifications_from(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>> {
         #[derive(Deserialize)]
         struct FlareEvent {
             #[serde(rename = "flrID")]
@@ -68,18 +107,7 @@ impl NotificationService for Service {
             link: String,
         }
 
-        let api_key = std::env::var("NASA_API_KEY")
-            .unwrap_or_else(|_| "DEMO_KEY".to_string());
-        let start_date = (chrono::Utc::now() - chrono::Duration::hours(24)).format("%Y-%m-%d");
-        let url = format!("https://api.nasa.gov/DONKI/FLR?startDate={}&api_key={}", start_date, api_key);
-        
-        let res = self.client.get(&url).send().await?;
-        
-        if !res.status().is_success() {
-             return Err(anyhow::anyhow!("NASA API returned status {}", res.status()));
-        }
-
-        let data: Vec<FlareEvent> = res.json().await?;
+        let data: Vec<FlareEvent> = serde_json::from_value(response)?;
 
         let mut notifications: Vec<Box<dyn Notification>> = Vec::new();
         for event in data {
@@ -1,5 +1,5 @@
-use super::{Notification, NotificationService};
-use crate::{config::ServiceConfig, state::Manager, telegram};
+use super::{Field, Link, Message, Notification, NotificationService, Severity};
+use crate::{config::ServiceConfig, state::Manager};
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::time::Duration;
@@ -34,31 +34,37 @@ pub struct VulnerabilityNotification {
 impl Notification for VulnerabilityNotification {
     fn get_unique_id(&self) -> &str { &self.id }
     fn get_timestamp(&self) -> i64 { self.time }
-    fn format_message(&self) -> String {
-        let title = "🚨 *Critical Vulnerability Alert* 🚨";
-        let time_str = chrono::DateTime::from_timestamp(self.time, 0)
-            .map(|t| t.format("%c").to_string())
-            .unwrap_or_else(|| "N/A".to_string());
-        
-        let score_str = telegram::escape_markdown(&format!("{:.1}", self.score));
-
-        let description_block = if !self.description.is_empty() {
-            format!("*Description:*\n```\n{}\n```\n", self.description)
+    fn score(&self) -> Option<f64> { Some(self.score) }
+    fn message(&self) -> Message {
+        let severity = if self.score >= 9.5 {
+            Severity::Critical
+        } else if self.score >= 9.0 {
+            Severity::Major
+        } else if self.score >= 7.0 {
+            Severity::Minor
         } else {
-            "".to_string()
+            Severity::Info
         };
 
-        format!(
-            "{}\n\n*CVE ID:* {}\n*Severity:* {} \\(Score: {}\\)\n*Published:* {}\n{}{}\n*Details:* [NVD Details]({})",
-            title,
-            telegram::escape_markdown(&self.id),
-            telegram::escape_markdown(&self.severity),
-            score_str,
-            telegram::escape_markdown(&time_str),
-            description_block,
-            if !description_block.is_empty() { "" } else { "\n" }, // Add newline if no description
-            self.url
-        )
+        let mut fields = vec![
+            Field::text("cve_id", "CVE ID", self.id.clone()),
+            Field::text("severity", "Severity", self.severity.clone()),
+            Field::number("cvss_score", "CVSS Score", self.score, 1),
+            Field::time("published", "Published", self.time),
+        ];
+        if !self.description.is_empty() {
+            fields.push(Field::text("description", "Description", self.description.clone()));
+        }
+
+        Message {
+            title: "🚨 Critical Vulnerability Alert 🚨".to_string(),
+            severity,
+            fields,
+            links: vec![Link::new("NVD Details", self.url.clone())],
+            geo: None,
+            tags: vec!["cve".to_string(), "security".to_string()],
+            updated: false,
+        }
     }
 }
 
@@ -67,8 +73,47 @@ impl NotificationService for Service {
     fn name(&self) -> &str { "Vulnerability" }
     fn get_config(&self) -> &ServiceConfig { &self.config }
     fn get_state_manager(&self) -> &Manager { &self.state }
+    fn score_name(&self) -> Option<&str> { Some("CVSS score") }
+
+    fn reconfigured(&self, config: ServiceConfig) -> Box<dyn NotificationService> {
+        Box::new(Service {
+            state: self.state.clone(),
+            config,
+            client: self.client.clone(),
+        })
+    }
 
     async fn check_for_notifications(&self) -> anyhow::Result<Vec<Box<dyn Notification>>> {
+        let severities_to_fetch = ["CRITICAL"];
+        let mut all_notifications: Vec<Box<dyn Notification>> = Vec::new();
+
+        for severity in severities_to_fetch {
+            info!(severity, "Checking for vulnerabilities...");
+
+            let pub_end_date = chrono::Utc::now();
+            let pub_start_date = pub_end_date - chrono::Duration::hours(1);
+
+            let url = format!(
+                "https://services.nvd.nist.gov/rest/json/cves/2.0?pubStartDate={}&pubEndDate={}&cvssV3Severity={}",
+                pub_start_date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                pub_end_date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                severity
+            );
+
+            let res = self.client.get(&url).send().await?;
+            if !res.status().is_success() {
+                warn!(status = %res.status(), "NVD API returned non-200 status for severity {}", severity);
+                continue;
+            }
+
+            all_notifications.extend(self.notifications_from(res.json().await?).await?);
+            tokio::time::sleep(Duration::from_secs(6)).await;
+        }
+
+        Ok(all_notifications)
+    }
+
+    async fn notifications_from(&self, response: serde_json::Value) -> anyhow::Result<Vec<Box<dyn Notification>>> {
         #[derive(Deserialize, Debug)]
         struct CveItem {
             cve: Cve,
@@ -113,58 +158,35 @@ impl NotificationService for Service {
             vulnerabilities: Vec<CveItem>,
         }
 
-        let severities_to_fetch = ["CRITICAL"];
-        let mut all_notifications: Vec<Box<dyn Notification>> = Vec::new();
-
-        for severity in severities_to_fetch {
-            info!(severity, "Checking for vulnerabilities...");
-
-            let pub_end_date = chrono::Utc::now();
-            let pub_start_date = pub_end_date - chrono::Duration::hours(1);
-
-            let url = format!(
-                "https://services.nvd.nist.gov/rest/json/cves/2.0?pubStartDate={}&pubEndDate={}&cvssV3Severity={}",
-                pub_start_date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                pub_end_date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                severity
-            );
-
-            let res = self.client.get(&url).send().await?;
-            if !res.status().is_success() {
-                warn!(status = %res.status(), "NVD API returned non-200 status for severity {}", severity);
-                continue;
-            }
-
-            let data: NvdResponse = res.json().await?;
-
-            for item in data.vulnerabilities {
-                let cve = item.cve;
-                if !self.state.is_seen(&cve.id).await {
-                    let pub_time = match chrono::DateTime::parse_from_rfc3339(&cve.published) {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    };
+        let data: NvdResponse = serde_json::from_value(response)?;
+        let mut notifications: Vec<Box<dyn Notification>> = Vec::new();
+
+        for item in data.vulnerabilities {
+            let cve = item.cve;
+            if !self.state.is_seen(&cve.id).await {
+                let pub_time = match chrono::DateTime::parse_from_rfc3339(&cve.published) {
+                    Ok(t) => t,
+                    Err(_) => continue,
+                };
+                
+                if let Some(metric) = cve.metrics.cvss_metric_v31.first() {
+                    let desc = cve.descriptions.iter().find(|d| d.lang == "en").map_or("", |d| &d.value);
                     
-                    if let Some(metric) = cve.metrics.cvss_metric_v31.first() {
-                        let desc = cve.descriptions.iter().find(|d| d.lang == "en").map_or("", |d| &d.value);
-                        
-                        let notif = Box::new(VulnerabilityNotification {
-                            id: cve.id.clone(),
-                            severity: metric.cvss_data.base_severity.clone(),
-                            score: metric.cvss_data.base_score,
-                            description: desc.to_string(),
-                            url: format!("https://nvd.nist.gov/vuln/detail/{}", cve.id),
-                            time: pub_time.timestamp(),
-                        });
-
-                        all_notifications.push(notif);
-                        self.state.add(cve.id, pub_time.timestamp()).await;
-                    }
+                    let notif = Box::new(VulnerabilityNotification {
+                        id: cve.id.clone(),
+                        severity: metric.cvss_data.base_severity.clone(),
+                        score: metric.cvss_data.base_score,
+                        description: desc.to_string(),
+                        url: format!("https://nvd.nist.gov/vuln/detail/{}", cve.id),
+                        time: pub_time.timestamp(),
+                    });
+
+                    notifications.push(notif);
+                    self.state.add(cve.id, pub_time.timestamp()).await;
                 }
             }
-            tokio::time::sleep(Duration::from_secs(6)).await;
         }
 
-        Ok(all_notifications)
+        Ok(notifications)
     }
 }
\ No newline at end of file
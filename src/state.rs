@@ -7,15 +7,44 @@ use tokio::sync::RwLock;
 use tracing::{info, warn};
 
 #[derive(Serialize, Deserialize, Debug)]
-struct Entry {
-    id: String,
+pub struct Entry {
+    pub id: String,
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<SentMessage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SentMessage {
+    pub chat_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<i64>,
+    pub message_id: i64,
+    // The report was split over several messages, starting with `message_id`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub split: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Record {
     timestamp: i64,
+    revision: Option<String>,
+    messages: Vec<SentMessage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observation {
+    New,
+    Revised,
+    Unchanged,
 }
 
 #[derive(Debug, Clone)]
 pub struct Manager {
     file_path: PathBuf,
-    seen_ids: Arc<RwLock<HashMap<String, i64>>>,
+    seen_ids: Arc<RwLock<HashMap<String, Record>>>,
     memory_duration: Duration,
 }
 
@@ -35,7 +64,56 @@ impl Manager {
 
     pub async fn add(&self, id: String, timestamp: i64) {
         let mut seen = self.seen_ids.write().await;
-        seen.insert(id, timestamp);
+        seen.entry(id).or_default().timestamp = timestamp;
+    }
+
+    // Records the current revision of an upstream record (e.g. a magnitude or launch time)
+    // and reports whether it is new or `revised(previous, current)` since it was last reported.
+    // Unchanged revisions are not stored, so small changes cannot add up unnoticed.
+    pub async fn observe(&self, id: &str, timestamp: i64, revision: String, revised: impl FnOnce(&str, &str) -> bool) -> Observation {
+        let mut seen = self.seen_ids.write().await;
+        let Some(record) = seen.get_mut(id) else {
+            seen.insert(
+                id.to_string(),
+                Record {
+                    timestamp,
+                    revision: Some(revision),
+                    messages: Vec::new(),
+                },
+            );
+            return Observation::New;
+        };
+
+        record.timestamp = timestamp;
+        match &record.revision {
+            Some(previous) if revised(previous, &revision) => {
+                record.revision = Some(revision);
+                Observation::Revised
+            }
+            Some(_) => Observation::Unchanged,
+            // Entries saved before revisions were tracked only pick up the current one.
+            None => {
+                record.revision = Some(revision);
+                Observation::Unchanged
+            }
+        }
+    }
+
+    pub async fn add_message(&self, id: &str, message: SentMessage) {
+        let mut seen = self.seen_ids.write().await;
+        if let Some(record) = seen.get_mut(id) {
+            record.messages.retain(|m| m.chat_id != message.chat_id || m.thread_id != message.thread_id);
+            record.messages.push(message);
+        }
+    }
+
+    pub async fn message(&self, id: &str, chat_id: &str, thread_id: Option<i64>) -> Option<SentMessage> {
+        let seen = self.seen_ids.read().await;
+        seen.get(id)?
+            .messages
+            .iter()
+            .find(|m| m.chat_id == chat_id && m.thread_id == thread_id)
+            .cloned()
     }
 
     pub async fn load(&self) -> anyhow::Result<()> {
@@ -51,7 +129,14 @@ impl Manager {
                 let mut loaded_count = 0;
                 for entry in entries {
                     if entry.timestamp >= cutoff {
-                        seen_map.insert(entry.id, entry.timestamp);
+                        seen_map.insert(
+                            entry.id,
+                            Record {
+                                timestamp: entry.timestamp,
+                                revision: entry.revision,
+                                messages: entry.messages,
+                            },
+                        );
                         loaded_count += 1;
                     }
                 }
@@ -67,18 +152,76 @@ impl Manager {
         Ok(())
     }
 
-    pub async fn save(&self) -> anyhow::Result<()> {
+    // Oldest first, in the form they are saved in.
+    pub async fn entries(&self) -> Vec<Entry> {
         let seen_map = self.seen_ids.read().await;
-        let entries: Vec<Entry> = seen_map
+        let mut entries: Vec<Entry> = seen_map
             .iter()
-            .map(|(id, &ts)| Entry { 
+            .map(|(id, record)| Entry { 
                 id: id.clone(),
-                timestamp: ts, 
+                timestamp: record.timestamp, 
+                revision: record.revision.clone(),
+                messages: record.messages.clone(),
             })
             .collect();
-        
+        entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+        entries
+    }
+
+    // Forgets every seen ID, so the next check notifies about everything again.
+    pub async fn purge(&self) {
+        self.seen_ids.write().await.clear();
+    }
+
+    pub async fn save(&self) -> anyhow::Result<()> {
+        let entries = self.entries().await;
         let data = serde_json::to_vec_pretty(&entries)?;
         tokio::fs::write(&self.file_path, data).await?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn differs(previous: &str, current: &str) -> bool {
+        previous != current
+    }
+
+    #[tokio::test]
+    async fn observe_new_revised_and_unchanged() {
+        let state = Manager::new("unused.json", Duration::ZERO);
+
+        assert_eq!(state.observe("a", 10, "1".to_string(), differs).await, Observation::New);
+        assert_eq!(state.observe("a", 10, "1".to_string(), differs).await, Observation::Unchanged);
+        assert_eq!(state.observe("a", 20, "2".to_string(), differs).await, Observation::Revised);
+        assert_eq!(state.observe("a", 20, "2".to_string(), differs).await, Observation::Unchanged);
+        assert_eq!(state.observe("b", 30, "1".to_string(), differs).await, Observation::New);
+
+        let entries = state.entries().await;
+        assert_eq!(entries[0].timestamp, 20);
+        assert_eq!(entries[0].revision.as_deref(), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn observe_compares_against_the_last_reported_revision() {
+        let state = Manager::new("unused.json", Duration::ZERO);
+        let by_ten = |previous: &str, current: &str| current.parse::<i64>().unwrap() - previous.parse::<i64>().unwrap() >= 10;
+
+        state.observe("a", 0, "0".to_string(), by_ten).await;
+        assert_eq!(state.observe("a", 0, "6".to_string(), by_ten).await, Observation::Unchanged);
+        assert_eq!(state.observe("a", 0, "12".to_string(), by_ten).await, Observation::Revised);
+        assert_eq!(state.entries().await[0].revision.as_deref(), Some("12"));
+    }
+
+    // Entries saved before revisions were tracked have none to compare with.
+    #[tokio::test]
+    async fn observe_without_a_previous_revision() {
+        let state = Manager::new("unused.json", Duration::ZERO);
+        state.add("a".to_string(), 10).await;
+
+        assert_eq!(state.observe("a", 10, "1".to_string(), differs).await, Observation::Unchanged);
+        assert_eq!(state.observe("a", 10, "2".to_string(), differs).await, Observation::Revised);
+    }
+}
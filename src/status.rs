@@ -0,0 +1,43 @@
+use crate::services::Notification;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Clone, Default)]
+pub struct ServiceStatus {
+    pub last_check: Option<DateTime<Utc>>,
+    pub last_error: Option<(DateTime<Utc>, String)>,
+    // The most recent notification found since startup.
+    pub latest: Option<Arc<dyn Notification>>,
+}
+
+// What each service monitor has been up to, for the bot's /status and /latest commands.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    services: Arc<RwLock<HashMap<String, ServiceStatus>>>,
+}
+
+impl Registry {
+    pub async fn record_check(&self, service: &str, latest: Option<Arc<dyn Notification>>) {
+        let mut services = self.services.write().await;
+        let status = services.entry(service.to_string()).or_default();
+        status.last_check = Some(Utc::now());
+        if latest.is_some() {
+            status.latest = latest;
+        }
+    }
+
+    pub async fn record_error(&self, service: &str, error: String) {
+        let mut services = self.services.write().await;
+        let status = services.entry(service.to_string()).or_default();
+        let now = Utc::now();
+        status.last_check = Some(now);
+        status.last_error = Some((now, error));
+    }
+
+    pub async fn get(&self, service: &str) -> ServiceStatus {
+        let services = self.services.read().await;
+        services.get(service).cloned().unwrap_or_default()
+    }
+}
@@ -0,0 +1,285 @@
+use crate::services::{Field, GeoPoint, Message, Notification, Severity};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub chat_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<i64>,
+    pub service: String,
+    // Minimum `Notification::score`, e.g. a magnitude or CVSS score.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+    // Only notifications located within this area, e.g. earthquake epicentres.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub area: Option<Area>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_km: f64,
+}
+
+impl Area {
+    // Great-circle distance and initial bearing (degrees from north) from the area's centre.
+    fn distance_and_bearing(&self, geo: &GeoPoint) -> (f64, f64) {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let (lat1, lat2) = (self.latitude.to_radians(), geo.latitude.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (geo.longitude - self.longitude).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        let distance = 2.0 * EARTH_RADIUS_KM * a.sqrt().atan2((1.0 - a).sqrt());
+
+        let y = d_lon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+        let bearing = (y.atan2(x).to_degrees() + 360.0) % 360.0;
+        (distance, bearing)
+    }
+}
+
+fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing + 22.5) / 45.0) as usize % 8]
+}
+
+// A notification as seen from a subscriber's location.
+#[derive(Debug)]
+struct Nearby {
+    inner: Arc<dyn Notification>,
+    distance_km: f64,
+    bearing: f64,
+}
+
+impl Notification for Nearby {
+    fn get_unique_id(&self) -> &str { self.inner.get_unique_id() }
+    fn get_timestamp(&self) -> i64 { self.inner.get_timestamp() }
+    fn message(&self) -> Message {
+        let mut message = self.inner.message();
+        message.fields.push(Field::text(
+            "distance",
+            "Distance",
+            format!(
+                "{:.0} km {} of your location",
+                self.distance_km,
+                compass_point(self.bearing)
+            ),
+        ));
+        message
+    }
+    fn severity(&self) -> Severity { self.inner.severity() }
+    fn score(&self) -> Option<f64> { self.inner.score() }
+}
+
+impl Subscription {
+    // Returns the notification as this subscriber should receive it, or `None` if it does not
+    // match their preferences.
+    pub fn matching(&self, n: &Arc<dyn Notification>) -> Option<Arc<dyn Notification>> {
+        if let Some(min) = self.min_score
+            && !n.score().is_some_and(|score| score >= min)
+        {
+            return None;
+        }
+        let Some(area) = &self.area else {
+            return Some(n.clone());
+        };
+
+        let geo = n.message().geo?;
+        let (distance_km, bearing) = area.distance_and_bearing(&geo);
+        if distance_km > area.radius_km {
+            return None;
+        }
+        Some(Arc::new(Nearby {
+            inner: n.clone(),
+            distance_km,
+            bearing,
+        }))
+    }
+
+    fn is_for(&self, chat_id: &str, thread_id: Option<i64>) -> bool {
+        self.chat_id == chat_id && self.thread_id == thread_id
+    }
+}
+
+// Subscriptions made through the bot, persisted as JSON next to the seen_*.json state files.
+#[derive(Debug, Clone)]
+pub struct Store {
+    file_path: PathBuf,
+    subscriptions: Arc<RwLock<Vec<Subscription>>>,
+}
+
+impl Store {
+    pub fn new(file_name: impl AsRef<Path>) -> Self {
+        Self {
+            file_path: file_name.as_ref().to_path_buf(),
+            subscriptions: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    pub async fn load(&self) -> anyhow::Result<()> {
+        let path_str = self.file_path.to_string_lossy();
+        match tokio::fs::read(&self.file_path).await {
+            Ok(data) => {
+                let loaded: Vec<Subscription> = serde_json::from_slice(&data)?;
+                info!("[{}] Loaded {} subscriptions.", path_str, loaded.len());
+                *self.subscriptions.write().await = loaded;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("[{}] Subscriptions file not found. Starting fresh.", path_str);
+            }
+            Err(e) => {
+                warn!("[{}] Error reading subscriptions file: {}. Starting fresh.", path_str, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(&self, subscriptions: &[Subscription]) -> anyhow::Result<()> {
+        let data = serde_json::to_vec_pretty(subscriptions)?;
+        tokio::fs::write(&self.file_path, data).await?;
+        Ok(())
+    }
+
+    // Adds the subscription, replacing an existing one of the same chat to the same service.
+    pub async fn subscribe(&self, subscription: Subscription) -> anyhow::Result<()> {
+        let mut subscriptions = self.subscriptions.write().await;
+        subscriptions.retain(|s| !(s.is_for(&subscription.chat_id, subscription.thread_id) && s.service == subscription.service));
+        subscriptions.push(subscription);
+        self.save(&subscriptions).await
+    }
+
+    // Returns whether there was a subscription to remove.
+    pub async fn unsubscribe(&self, chat_id: &str, thread_id: Option<i64>, service: &str) -> anyhow::Result<bool> {
+        let mut subscriptions = self.subscriptions.write().await;
+        let before = subscriptions.len();
+        subscriptions.retain(|s| !(s.is_for(chat_id, thread_id) && s.service == service));
+        if subscriptions.len() == before {
+            return Ok(false);
+        }
+        self.save(&subscriptions).await?;
+        Ok(true)
+    }
+
+    pub async fn for_chat(&self, chat_id: &str, thread_id: Option<i64>) -> Vec<Subscription> {
+        let subscriptions = self.subscriptions.read().await;
+        subscriptions.iter().filter(|s| s.is_for(chat_id, thread_id)).cloned().collect()
+    }
+
+    pub async fn find(&self, chat_id: &str, thread_id: Option<i64>, service: &str) -> Option<Subscription> {
+        let subscriptions = self.subscriptions.read().await;
+        subscriptions
+            .iter()
+            .find(|s| s.is_for(chat_id, thread_id) && s.service == service)
+            .cloned()
+    }
+
+    pub async fn for_service(&self, service: &str) -> Vec<Subscription> {
+        let subscriptions = self.subscriptions.read().await;
+        subscriptions.iter().filter(|s| s.service == service).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64) -> GeoPoint {
+        GeoPoint {
+            latitude,
+            longitude,
+            label: String::new(),
+        }
+    }
+
+    #[test]
+    fn distance_and_bearing_between_cities() {
+        // Berlin to Paris is about 878 km to the west-southwest.
+        let berlin = Area { latitude: 52.52, longitude: 13.405, radius_km: 1000.0 };
+        let (distance, bearing) = berlin.distance_and_bearing(&point(48.8566, 2.3522));
+        assert!((distance - 878.0).abs() < 5.0, "distance {}", distance);
+        assert!((bearing - 246.0).abs() < 2.0, "bearing {}", bearing);
+        assert_eq!(compass_point(bearing), "SW");
+    }
+
+    #[test]
+    fn distance_and_bearing_along_axes() {
+        let origin = Area { latitude: 0.0, longitude: 0.0, radius_km: 100.0 };
+        let (distance, bearing) = origin.distance_and_bearing(&point(1.0, 0.0));
+        assert!((distance - 111.19).abs() < 0.1);
+        assert!(bearing.abs() < 1e-9);
+
+        let (_, bearing) = origin.distance_and_bearing(&point(0.0, -1.0));
+        assert!((bearing - 270.0).abs() < 1e-9);
+
+        let (distance, _) = origin.distance_and_bearing(&point(0.0, 0.0));
+        assert_eq!(distance, 0.0);
+    }
+
+    #[derive(Debug)]
+    struct Quake {
+        score: Option<f64>,
+        geo: Option<GeoPoint>,
+    }
+
+    impl Notification for Quake {
+        fn get_unique_id(&self) -> &str { "quake" }
+        fn get_timestamp(&self) -> i64 { 0 }
+        fn message(&self) -> Message {
+            Message {
+                title: "Earthquake".to_string(),
+                severity: Severity::Minor,
+                fields: Vec::new(),
+                links: Vec::new(),
+                geo: self.geo.clone(),
+                tags: Vec::new(),
+                updated: false,
+            }
+        }
+        fn score(&self) -> Option<f64> { self.score }
+    }
+
+    fn subscription(min_score: Option<f64>, area: Option<Area>) -> Subscription {
+        Subscription {
+            chat_id: "1".to_string(),
+            thread_id: None,
+            service: "Earthquake".to_string(),
+            min_score,
+            area,
+        }
+    }
+
+    fn quake(score: Option<f64>, geo: Option<GeoPoint>) -> Arc<dyn Notification> {
+        Arc::new(Quake { score, geo })
+    }
+
+    #[test]
+    fn matching_applies_the_minimum_score() {
+        let s = subscription(Some(5.0), None);
+        assert!(s.matching(&quake(Some(5.0), None)).is_some());
+        assert!(s.matching(&quake(Some(6.2), None)).is_some());
+        assert!(s.matching(&quake(Some(4.9), None)).is_none());
+        // Without a score there is nothing to compare the minimum with.
+        assert!(s.matching(&quake(None, None)).is_none());
+
+        assert!(subscription(None, None).matching(&quake(None, None)).is_some());
+    }
+
+    #[test]
+    fn matching_applies_the_area() {
+        let area = Area { latitude: 0.0, longitude: 0.0, radius_km: 200.0 };
+        let s = subscription(Some(5.0), Some(area));
+
+        let nearby = s.matching(&quake(Some(5.5), Some(point(1.0, 0.0)))).unwrap();
+        let distance = nearby.message().fields.pop().unwrap();
+        assert_eq!(distance.key, "distance");
+        assert!(s.matching(&quake(Some(5.5), Some(point(3.0, 0.0)))).is_none());
+        assert!(s.matching(&quake(Some(4.5), Some(point(1.0, 0.0)))).is_none());
+        assert!(s.matching(&quake(Some(5.5), None)).is_none());
+    }
+}